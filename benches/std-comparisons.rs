@@ -362,7 +362,7 @@ fn bench_btree(c: &mut Criterion) {
 
         b.iter(|| {
             let mut sum = 0u64;
-            for (_, value) in &tree {
+            for value in tree.values() {
                 sum = sum.wrapping_add(*value);
             }
             black_box(sum);
@@ -1,4 +1,8 @@
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::marker::PhantomData;
 use core::mem::MaybeUninit;
+use core::ops::Index;
 use core::ptr;
 
 use crate::unlikely;
@@ -54,17 +58,24 @@ where
         self.len == 0
     }
 
+    /// Upper bound on the number of keys, `ORDER * MAX_ORDER`: the key slots of the
+    /// whole node pool. This used to be `ORDER^4`, an estimate unrelated to the pool that
+    /// could promise far more room than the tree has.
+    ///
+    /// The bound is reached only if every node in the pool is a full leaf. Splits leave
+    /// nodes half full and internal nodes hold no entries, so inserts usually fail with
+    /// [`BTreeError::Full`] well before this.
     #[inline]
-    pub fn capacity(&self) -> usize {
-        ORDER * ORDER * ORDER * ORDER // approx
+    pub const fn capacity(&self) -> usize {
+        ORDER * MAX_ORDER
     }
 
     fn allocate_node(&mut self) -> Result<NodeIndex, BTreeError> {
         let mut index = self.next_free;
         let mut found = false;
 
-        for i in 0..64 {
-            let current_index = (self.next_free + i) % 64;
+        for i in 0..ORDER {
+            let current_index = (self.next_free + i) % ORDER;
             let is_free = self.free_list[current_index];
             index = (current_index & (is_free as usize).wrapping_sub(1))
                 | (index & ((!is_free) as usize).wrapping_sub(1));
@@ -80,7 +91,7 @@ where
         }
 
         self.free_list[index] = false;
-        self.next_free = (index + 1) % 64;
+        self.next_free = (index + 1) % ORDER;
         unsafe {
             let node = &mut *self.nodes[index].as_mut_ptr();
             ptr::write(
@@ -100,7 +111,7 @@ where
     }
 
     fn deallocate_node(&mut self, index: NodeIndex) {
-        if index < 64 {
+        if index < ORDER {
             unsafe {
                 let node = &mut *self.nodes[index].as_mut_ptr();
                 for i in 0..node.key_count {
@@ -165,15 +176,71 @@ where
         }
 
         let root_index = self.root.unwrap();
-        self.insert_recursive(root_index, key, value)
+
+        // make sure every split the insert causes can get a node before touching anything
+        if let Some(needed) = self.nodes_needed(root_index, &key)
+            && needed > self.free_nodes()
+        {
+            return Err(BTreeError::Full);
+        }
+
+        let (old_value, split) = self.insert_recursive(root_index, key, value);
+        if let Some((separator, right_index)) = split {
+            let new_root = self.allocate_node()?;
+            let root = unsafe { &mut *self.nodes[new_root].as_mut_ptr() };
+            unsafe {
+                ptr::write(root.keys[0].as_mut_ptr(), separator);
+            }
+            root.children[0] = Some(root_index);
+            root.children[1] = Some(right_index);
+            root.key_count = 1;
+            root.is_leaf = false;
+            self.root = Some(new_root);
+        }
+
+        Ok(old_value)
+    }
+
+    /// Returns how many nodes inserting `key` would allocate, or `None` if it's already present
+    fn nodes_needed(&self, root_index: NodeIndex, key: &K) -> Option<usize> {
+        let mut current = root_index;
+        let mut full_run = 0;
+        let mut depth = 0;
+
+        loop {
+            let node = unsafe { &*self.nodes[current].as_ptr() };
+            let (found, pos) = self.search_node(current, key);
+
+            depth += 1;
+            full_run = if node.key_count >= MAX_ORDER {
+                full_run + 1
+            } else {
+                0
+            };
+
+            if node.is_leaf {
+                // a split that reaches the root also needs a new root
+                return (!found).then_some(full_run + (full_run == depth) as usize);
+            }
+
+            current = node.children[pos + found as usize]?;
+        }
     }
 
+    fn free_nodes(&self) -> usize {
+        self.free_list.iter().filter(|&&free| free).count()
+    }
+
+    /// Inserts into the subtree at `node_index`, returning the replaced value and,
+    /// if the node had to split, the separator and index of its new right sibling.
+    ///
+    /// Callers must check there are enough free nodes for the splits first.
     fn insert_recursive(
         &mut self,
         node_index: NodeIndex,
         key: K,
         value: V,
-    ) -> Result<Option<V>, BTreeError> {
+    ) -> (Option<V>, Option<(K, NodeIndex)>) {
         let node = unsafe { &mut *self.nodes[node_index].as_mut_ptr() };
         let (found, pos) = self.search_node(node_index, &key);
 
@@ -183,33 +250,142 @@ where
                 unsafe {
                     ptr::write(node.values[pos].as_mut_ptr(), value);
                 }
-                return Ok(Some(old_value));
+                return (Some(old_value), None);
             }
 
-            if node.key_count >= ORDER {
-                return Err(BTreeError::Full);
+            self.len += 1;
+            if node.key_count < MAX_ORDER {
+                unsafe { Self::leaf_insert_at(node, pos, key, value) };
+                return (None, None);
             }
 
-            for i in (pos..node.key_count).rev() {
-                unsafe {
-                    let src_key = ptr::read(node.keys[i].as_ptr());
-                    let src_value = ptr::read(node.values[i].as_ptr());
-                    ptr::write(node.keys[i + 1].as_mut_ptr(), src_key);
-                    ptr::write(node.values[i + 1].as_mut_ptr(), src_value);
-                }
+            let right_index = self.split_leaf(node_index);
+            let left = unsafe { &mut *self.nodes[node_index].as_mut_ptr() };
+            let right = unsafe { &mut *self.nodes[right_index].as_mut_ptr() };
+            if pos <= left.key_count {
+                unsafe { Self::leaf_insert_at(left, pos, key, value) };
+            } else {
+                unsafe { Self::leaf_insert_at(right, pos - left.key_count, key, value) };
             }
 
-            unsafe {
-                ptr::write(node.keys[pos].as_mut_ptr(), key);
-                ptr::write(node.values[pos].as_mut_ptr(), value);
-            }
-            node.key_count += 1;
-            self.len += 1;
-            Ok(None)
+            let separator = unsafe { *right.keys[0].as_ptr() };
+            (None, Some((separator, right_index)))
         } else {
-            let child_index = node.children[pos + found as usize].unwrap();
-            self.insert_recursive(child_index, key, value)
+            let child_pos = pos + found as usize;
+            let child_index = node.children[child_pos].unwrap();
+            let (old_value, split) = self.insert_recursive(child_index, key, value);
+
+            let Some((separator, new_child)) = split else {
+                return (old_value, None);
+            };
+
+            let node = unsafe { &mut *self.nodes[node_index].as_mut_ptr() };
+            if node.key_count < MAX_ORDER {
+                unsafe { Self::internal_insert_at(node, child_pos, separator, new_child) };
+                return (old_value, None);
+            }
+
+            let (promoted, right_index) = self.split_internal(node_index);
+            let left = unsafe { &mut *self.nodes[node_index].as_mut_ptr() };
+            let right = unsafe { &mut *self.nodes[right_index].as_mut_ptr() };
+            if child_pos <= left.key_count {
+                unsafe { Self::internal_insert_at(left, child_pos, separator, new_child) };
+            } else {
+                let right_pos = child_pos - left.key_count - 1;
+                unsafe { Self::internal_insert_at(right, right_pos, separator, new_child) };
+            }
+
+            (old_value, Some((promoted, right_index)))
+        }
+    }
+
+    /// Moves the upper half of a full leaf into a new leaf linked after it
+    fn split_leaf(&mut self, left_index: NodeIndex) -> NodeIndex {
+        let right_index = self
+            .allocate_node()
+            .expect("BTree split without a free node");
+        let left = unsafe { &mut *self.nodes[left_index].as_mut_ptr() };
+        let right = unsafe { &mut *self.nodes[right_index].as_mut_ptr() };
+
+        let mid = left.key_count / 2;
+        let moved = left.key_count - mid;
+        unsafe {
+            ptr::copy_nonoverlapping(left.keys[mid].as_ptr(), right.keys[0].as_mut_ptr(), moved);
+            ptr::copy_nonoverlapping(
+                left.values[mid].as_ptr(),
+                right.values[0].as_mut_ptr(),
+                moved,
+            );
+        }
+
+        right.key_count = moved;
+        left.key_count = mid;
+        right.next_leaf = left.next_leaf;
+        left.next_leaf = Some(right_index);
+
+        right_index
+    }
+
+    /// Moves the upper half of a full internal node into a new node,
+    /// returning the middle key that now separates them
+    fn split_internal(&mut self, left_index: NodeIndex) -> (K, NodeIndex) {
+        let right_index = self
+            .allocate_node()
+            .expect("BTree split without a free node");
+        let left = unsafe { &mut *self.nodes[left_index].as_mut_ptr() };
+        let right = unsafe { &mut *self.nodes[right_index].as_mut_ptr() };
+
+        let mid = left.key_count / 2;
+        let moved = left.key_count - mid - 1;
+        let promoted = unsafe { *left.keys[mid].as_ptr() };
+        unsafe {
+            ptr::copy_nonoverlapping(
+                left.keys[mid + 1].as_ptr(),
+                right.keys[0].as_mut_ptr(),
+                moved,
+            );
+        }
+        for i in 0..=moved {
+            right.children[i] = left.children[mid + 1 + i].take();
         }
+
+        right.key_count = moved;
+        right.is_leaf = false;
+        left.key_count = mid;
+
+        (promoted, right_index)
+    }
+
+    /// # Safety
+    ///
+    /// `node` must be a leaf with room for one more key, and `pos <= node.key_count`
+    unsafe fn leaf_insert_at(node: &mut Node<K, V>, pos: usize, key: K, value: V) {
+        let count = node.key_count - pos;
+        unsafe {
+            let keys = node.keys.as_mut_ptr();
+            let values = node.values.as_mut_ptr();
+            ptr::copy(keys.add(pos), keys.add(pos + 1), count);
+            ptr::copy(values.add(pos), values.add(pos + 1), count);
+            ptr::write(node.keys[pos].as_mut_ptr(), key);
+            ptr::write(node.values[pos].as_mut_ptr(), value);
+        }
+        node.key_count += 1;
+    }
+
+    /// # Safety
+    ///
+    /// `node` must be an internal node with room for one more key, and `pos <= node.key_count`
+    unsafe fn internal_insert_at(node: &mut Node<K, V>, pos: usize, key: K, right: NodeIndex) {
+        let count = node.key_count - pos;
+        unsafe {
+            let keys = node.keys.as_mut_ptr();
+            ptr::copy(keys.add(pos), keys.add(pos + 1), count);
+            ptr::write(node.keys[pos].as_mut_ptr(), key);
+        }
+        node.children
+            .copy_within(pos + 1..node.key_count + 1, pos + 2);
+        node.children[pos + 1] = Some(right);
+        node.key_count += 1;
     }
 
     pub fn get(&self, key: &K) -> Option<&V> {
@@ -279,6 +455,36 @@ where
         }
     }
 
+    pub fn iter_mut(&mut self) -> BTreeIterMut<'_, K, V, ORDER> {
+        BTreeIterMut {
+            current_node: self.find_leftmost_leaf(),
+            nodes: self.nodes.as_mut_ptr(),
+            current_pos: 0,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Builds a tree from an iterator, returning an error if it doesn't fit
+    pub fn try_from_iter<I>(iter: I) -> Result<Self, BTreeError>
+    where
+        I: IntoIterator<Item = (K, V)>,
+    {
+        let mut tree = Self::new();
+        tree.try_extend(iter)?;
+        Ok(tree)
+    }
+
+    /// Inserts every pair from an iterator, stopping at the first one that doesn't fit
+    pub fn try_extend<I>(&mut self, iter: I) -> Result<(), BTreeError>
+    where
+        I: IntoIterator<Item = (K, V)>,
+    {
+        for (key, value) in iter {
+            self.insert(key, value)?;
+        }
+        Ok(())
+    }
+
     fn find_leftmost_leaf(&self) -> Option<NodeIndex> {
         let mut current = self.root?;
 
@@ -328,7 +534,7 @@ where
 
 impl<K, V, const ORDER: usize> BTree<K, V, ORDER> {
     fn drop_recursive(&mut self, node_index: NodeIndex) {
-        if node_index < 64 {
+        if node_index < ORDER {
             unsafe {
                 let node = &mut *self.nodes[node_index].as_mut_ptr();
                 for i in 0..node.key_count {
@@ -369,29 +575,23 @@ impl<'a, K, V, const ORDER: usize> Iterator for BTreeIter<'a, K, V, ORDER> {
 
     #[inline]
     fn next(&mut self) -> Option<Self::Item> {
-        let node_index = self.current_node?;
-        let node = unsafe { &*self.tree.nodes[node_index].as_ptr() };
-        if self.current_pos >= node.key_count {
-            self.current_node = node.next_leaf;
-            self.current_pos = 0;
-
-            let next_node_index = self.current_node?;
-            let next_node = unsafe { &*self.tree.nodes[next_node_index].as_ptr() };
-            if next_node.key_count == 0 {
-                return None;
+        loop {
+            let node_index = self.current_node?;
+            let node = unsafe { &*self.tree.nodes[node_index].as_ptr() };
+
+            // removals can leave empty leaves behind, so keep walking the chain
+            if self.current_pos >= node.key_count {
+                self.current_node = node.next_leaf;
+                self.current_pos = 0;
+                continue;
             }
 
-            let key = unsafe { &*next_node.keys[0].as_ptr() };
-            let value = unsafe { &*next_node.values[0].as_ptr() };
-            self.current_pos = 1;
+            let key = unsafe { &*node.keys[self.current_pos].as_ptr() };
+            let value = unsafe { &*node.values[self.current_pos].as_ptr() };
+            self.current_pos += 1;
+
             return Some((key, value));
         }
-
-        let key = unsafe { &*node.keys[self.current_pos].as_ptr() };
-        let value = unsafe { &*node.values[self.current_pos].as_ptr() };
-        self.current_pos += 1;
-
-        Some((key, value))
     }
 
     #[inline]
@@ -400,6 +600,196 @@ impl<'a, K, V, const ORDER: usize> Iterator for BTreeIter<'a, K, V, ORDER> {
     }
 }
 
+/// Mutable iterator over tree entries in key order, keys are still shared
+pub struct BTreeIterMut<'a, K, V, const ORDER: usize> {
+    nodes: *mut MaybeUninit<Node<K, V>>,
+    current_node: Option<NodeIndex>,
+    current_pos: usize,
+    _marker: PhantomData<&'a mut BTree<K, V, ORDER>>,
+}
+
+impl<'a, K, V, const ORDER: usize> Iterator for BTreeIterMut<'a, K, V, ORDER> {
+    type Item = (&'a K, &'a mut V);
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let node_index = self.current_node?;
+            let node = unsafe { &mut *(*self.nodes.add(node_index)).as_mut_ptr() };
+
+            if self.current_pos >= node.key_count {
+                self.current_node = node.next_leaf;
+                self.current_pos = 0;
+                continue;
+            }
+
+            // every position is visited once, so the references never alias
+            let key = unsafe { &*node.keys[self.current_pos].as_ptr() };
+            let value = unsafe { &mut *node.values[self.current_pos].as_mut_ptr() };
+            self.current_pos += 1;
+
+            return Some((key, value));
+        }
+    }
+}
+
+impl<K, V, const ORDER: usize> Clone for BTree<K, V, ORDER>
+where
+    K: Ord + Copy,
+    V: Clone,
+{
+    /// Copies the tree node for node, so the clone always fits
+    fn clone(&self) -> Self {
+        let mut tree = Self::new();
+
+        for index in 0..ORDER {
+            if self.free_list[index] {
+                continue;
+            }
+
+            let node = unsafe { &*self.nodes[index].as_ptr() };
+            let copy = unsafe { &mut *tree.nodes[index].as_mut_ptr() };
+            unsafe {
+                ptr::write(
+                    copy,
+                    Node {
+                        keys: MaybeUninit::uninit().assume_init(),
+                        values: MaybeUninit::uninit().assume_init(),
+                        children: node.children,
+                        next_leaf: node.next_leaf,
+                        key_count: 0,
+                        is_leaf: node.is_leaf,
+                    },
+                );
+                ptr::copy_nonoverlapping(
+                    node.keys[0].as_ptr(),
+                    copy.keys[0].as_mut_ptr(),
+                    node.key_count,
+                );
+            }
+
+            if node.is_leaf {
+                for i in 0..node.key_count {
+                    let value = unsafe { &*node.values[i].as_ptr() };
+                    copy.values[i].write(value.clone());
+                }
+            }
+            copy.key_count = node.key_count;
+            tree.free_list[index] = false;
+        }
+
+        // the root goes in last, so a panicking `V::clone` leaks the copied values instead of
+        // dropping through half-built nodes
+        tree.next_free = self.next_free;
+        tree.len = self.len;
+        tree.root = self.root;
+        tree
+    }
+}
+
+impl<K, V, const ORDER: usize> fmt::Debug for BTree<K, V, ORDER>
+where
+    K: Ord + Copy + fmt::Debug,
+    V: Clone + fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<K, V, const ORDER: usize> PartialEq for BTree<K, V, ORDER>
+where
+    K: Ord + Copy,
+    V: Clone + PartialEq,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.len == other.len && self.iter().eq(other.iter())
+    }
+}
+
+impl<K, V, const ORDER: usize> Eq for BTree<K, V, ORDER>
+where
+    K: Ord + Copy,
+    V: Clone + Eq,
+{
+}
+
+/// Entries are hashed in key order, so equal trees hash the same regardless of shape
+impl<K, V, const ORDER: usize> Hash for BTree<K, V, ORDER>
+where
+    K: Ord + Copy + Hash,
+    V: Clone + Hash,
+{
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_usize(self.len);
+        for entry in self.iter() {
+            entry.hash(state);
+        }
+    }
+}
+
+impl<K, V, const ORDER: usize> Index<&K> for BTree<K, V, ORDER>
+where
+    K: Ord + Copy,
+    V: Clone,
+{
+    type Output = V;
+
+    /// Panics if the key isn't present
+    fn index(&self, key: &K) -> &Self::Output {
+        self.get(key).expect("Key not found")
+    }
+}
+
+impl<'a, K, V, const ORDER: usize> IntoIterator for &'a BTree<K, V, ORDER>
+where
+    K: Ord + Copy,
+    V: Clone,
+{
+    type Item = (&'a K, &'a V);
+    type IntoIter = BTreeIter<'a, K, V, ORDER>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, K, V, const ORDER: usize> IntoIterator for &'a mut BTree<K, V, ORDER>
+where
+    K: Ord + Copy,
+    V: Clone,
+{
+    type Item = (&'a K, &'a mut V);
+    type IntoIter = BTreeIterMut<'a, K, V, ORDER>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+/// Panics if the iterator yields more pairs than the tree can hold,
+/// use [`BTree::try_from_iter`] to handle that case.
+impl<K, V, const ORDER: usize> FromIterator<(K, V)> for BTree<K, V, ORDER>
+where
+    K: Ord + Copy,
+    V: Clone,
+{
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        Self::try_from_iter(iter).expect("BTree capacity exceeded")
+    }
+}
+
+/// Panics if the tree runs out of nodes, use [`BTree::try_extend`] to handle that case.
+impl<K, V, const ORDER: usize> Extend<(K, V)> for BTree<K, V, ORDER>
+where
+    K: Ord + Copy,
+    V: Clone,
+{
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        self.try_extend(iter).expect("BTree capacity exceeded");
+    }
+}
+
 unsafe impl<K, V, const ORDER: usize> Send for BTree<K, V, ORDER>
 where
     K: Send,
@@ -530,4 +920,143 @@ mod tests {
             assert_eq!(tree.get(&(i * 2 + 1)), None);
         }
     }
+
+    #[test]
+    fn test_leaf_splits() {
+        let mut tree = BTree::<u32, u32, 16>::new();
+        // insert out of order so splits land in the middle of leaves too
+        for i in 0..600 {
+            let key = (i * 7919) % 600;
+            assert!(tree.insert(key, key * 2).unwrap().is_none());
+        }
+
+        assert_eq!(tree.len(), 600);
+        for key in 0..600 {
+            assert_eq!(tree.get(&key), Some(&(key * 2)));
+        }
+        let keys: Vec<_> = tree.iter().map(|(k, _)| *k).collect();
+        assert_eq!(keys, (0..600).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_internal_splits() {
+        // enough keys to overflow the root's children, run with room for the big tree
+        let handle = std::thread::Builder::new()
+            .stack_size(32 * 1024 * 1024)
+            .spawn(|| {
+                let mut tree = BTree::<u32, u8, 320>::new();
+                for key in (0..10_000).rev() {
+                    tree.insert(key, key as u8).unwrap();
+                }
+
+                assert_eq!(tree.len(), 10_000);
+                for key in (0..10_000).step_by(37) {
+                    assert_eq!(tree.get(&key), Some(&(key as u8)));
+                }
+                assert!(tree.iter().map(|(k, _)| *k).eq(0..10_000));
+            })
+            .unwrap();
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn test_full_without_losing_entries() {
+        let mut tree = BTree::<u32, u32, 2>::new();
+        for i in 0..MAX_ORDER as u32 {
+            tree.insert(i, i).unwrap();
+        }
+
+        // the next key splits the root leaf, which needs a leaf and a new root
+        assert_eq!(tree.insert(1000, 0), Err(BTreeError::Full));
+        assert_eq!(tree.len(), MAX_ORDER);
+        assert_eq!(tree.insert(5, 50), Ok(Some(5)));
+        assert!(tree.iter().map(|(k, _)| *k).eq(0..MAX_ORDER as u32));
+    }
+
+    #[test]
+    fn test_iter_skips_empty_leaves() {
+        let mut tree = BTree::<u32, u32, 8>::new();
+        for i in 0..300 {
+            tree.insert(i, i).unwrap();
+        }
+        for i in 0..200 {
+            tree.remove(&i);
+        }
+
+        assert_eq!(tree.len(), 100);
+        assert!(tree.iter().map(|(k, _)| *k).eq(200..300));
+    }
+
+    #[test]
+    fn test_iter_mut() {
+        let mut tree: BTree<u32, u32, 8> = (0..200).map(|i| (i, i)).collect();
+        for (key, value) in &mut tree {
+            *value += key;
+        }
+        for i in 0..200 {
+            assert_eq!(tree[&i], i * 2);
+        }
+    }
+
+    #[test]
+    fn test_clone_eq() {
+        let mut tree: BTree<u32, std::string::String, 8> =
+            (0..200).map(|i| (i, std::format!("value{}", i))).collect();
+        let snapshot = tree.clone();
+        assert_eq!(snapshot, tree);
+
+        tree.insert(7, "changed".into()).unwrap();
+        assert_ne!(snapshot, tree);
+        assert_eq!(snapshot[&7], "value7");
+        assert_eq!(snapshot.len(), 200);
+    }
+
+    #[test]
+    fn test_debug_hash() {
+        use std::hash::{BuildHasher, RandomState};
+
+        let a: BTree<u32, u32, 8> = [(2, 20), (1, 10)].into_iter().collect();
+        let b: BTree<u32, u32, 8> = [(1, 10), (2, 20)].into_iter().collect();
+        assert_eq!(std::format!("{:?}", a), "{1: 10, 2: 20}");
+
+        let state = RandomState::new();
+        assert_eq!(state.hash_one(&a), state.hash_one(&b));
+    }
+
+    #[test]
+    fn test_try_from_iter() {
+        let pairs = (0..MAX_ORDER as u32 + 1).map(|i| (i, i));
+        assert_eq!(
+            BTree::<u32, u32, 2>::try_from_iter(pairs).err(),
+            Some(BTreeError::Full)
+        );
+
+        let mut tree = BTree::<u32, u32, 4>::new();
+        assert_eq!(tree.try_extend((0..10).map(|i| (i, i))), Ok(()));
+        assert_eq!(tree.len(), 10);
+    }
+
+    #[test]
+    #[should_panic(expected = "BTree capacity exceeded")]
+    fn test_extend_overflow() {
+        let mut tree = BTree::<u32, u32, 1>::new();
+        tree.extend((0..MAX_ORDER as u32 + 1).map(|i| (i, i)));
+    }
+
+    #[test]
+    fn test_capacity_bound() {
+        fn fill<const ORDER: usize>() -> (usize, usize) {
+            let mut tree = BTree::<u32, u32, ORDER>::new();
+            let mut key = 0;
+            while tree.insert(key, key).is_ok() {
+                key += 1;
+            }
+            (tree.len(), tree.capacity())
+        }
+
+        for (len, capacity) in [fill::<1>(), fill::<2>(), fill::<4>(), fill::<16>()] {
+            assert!(len >= MAX_ORDER);
+            assert!(len <= capacity);
+        }
+    }
 }
@@ -0,0 +1,381 @@
+use core::marker::PhantomData;
+use core::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+
+use crate::hashmap::{DEFAULT_LOAD, fnv1a_hash, load_limit};
+
+/// Slot words of 0 mean "no key" and "no value"
+const EMPTY: u64 = 0;
+
+/// Types that fit in a single `u64` and can be stored in a [`ConcurrentHashMap`]
+pub trait AtomicWord: Copy {
+    fn into_word(self) -> u64;
+    fn from_word(word: u64) -> Self;
+}
+
+macro_rules! impl_atomic_word {
+    ($($ty:ty => $unsigned:ty),* $(,)?) => {
+        $(
+            impl AtomicWord for $ty {
+                #[inline]
+                fn into_word(self) -> u64 {
+                    self as $unsigned as u64
+                }
+
+                #[inline]
+                fn from_word(word: u64) -> Self {
+                    word as $unsigned as $ty
+                }
+            }
+        )*
+    };
+}
+
+impl_atomic_word!(
+    u8 => u8, u16 => u16, u32 => u32, u64 => u64, usize => usize,
+    i8 => u8, i16 => u16, i32 => u32, i64 => u64, isize => usize,
+);
+
+impl AtomicWord for bool {
+    #[inline]
+    fn into_word(self) -> u64 {
+        self as u64
+    }
+
+    #[inline]
+    fn from_word(word: u64) -> Self {
+        word != 0
+    }
+}
+
+/// Lock-free fixed capacity hash map for word-sized keys and values,
+/// using open addressing and linear probing over atomic slots.
+///
+/// Keys are claimed with a CAS and never move, so a removed key leaves its slot
+/// behind as a tombstone that the same key reuses when it comes back. Claimed
+/// slots count towards the load limit until [`clear`](Self::clear).
+///
+/// Words are stored offset by one so 0 can mark empty slots, which means a key or value
+/// whose word is `u64::MAX` (`u64::MAX`, `-1i64`, ...) can't be stored.
+///
+/// Like [`HashMap`](crate::HashMap), capacity must be a power of 2, and `LOAD` is the
+/// maximum load factor as a percentage of `N` (75% by default), which must leave at
+/// least one slot empty.
+#[repr(align(64))]
+pub struct ConcurrentHashMap<K, V, const N: usize, const LOAD: usize = DEFAULT_LOAD> {
+    keys: [AtomicU64; N],
+    values: [AtomicU64; N],
+    claimed: AtomicUsize,
+    len: AtomicUsize,
+    _marker: PhantomData<(K, V)>,
+}
+
+impl<K, V, const N: usize, const LOAD: usize> ConcurrentHashMap<K, V, N, LOAD>
+where
+    K: AtomicWord,
+    V: AtomicWord,
+{
+    const LOAD_CHECK: () = {
+        assert!(
+            LOAD > 0,
+            "ConcurrentHashMap load factor must be greater than 0"
+        );
+        assert!(
+            LOAD < 100,
+            "ConcurrentHashMap load factor must leave a slot empty"
+        );
+    };
+
+    /// Panics if `N` is not a power of 2 (or is 0)
+    ///
+    /// Fails to compile if `LOAD` isn't between 1 and 99:
+    ///
+    /// ```compile_fail
+    /// use sakurai::ConcurrentHashMap;
+    ///
+    /// let map = ConcurrentHashMap::<u8, u8, 8, 100>::new();
+    /// ```
+    pub const fn new() -> Self {
+        assert!(N > 0, "ConcurrentHashMap size must be greater than 0");
+        assert!(
+            N.is_power_of_two(),
+            "ConcurrentHashMap size must be a power of 2"
+        );
+        let () = Self::LOAD_CHECK;
+
+        Self {
+            keys: [const { AtomicU64::new(EMPTY) }; N],
+            values: [const { AtomicU64::new(EMPTY) }; N],
+            claimed: AtomicUsize::new(0),
+            len: AtomicUsize::new(0),
+            _marker: PhantomData,
+        }
+    }
+
+    #[inline]
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Maximum number of distinct keys the map accepts, `N * LOAD / 100` rounded down
+    #[inline]
+    pub const fn max_len(&self) -> usize {
+        load_limit(N, LOAD)
+    }
+
+    /// Number of keys with a value, which may be stale by the time it's read
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len.load(Ordering::Acquire)
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Inserts a key-value pair into the map, returning the old value if the key already exists
+    pub fn insert(&self, key: K, value: V) -> Result<Option<V>, ConcurrentHashMapError> {
+        let key = encode(key)?;
+        let value = encode(value)?;
+        let index = self.claim_slot(key)?;
+
+        let old = self.values[index].swap(value, Ordering::AcqRel);
+        if old == EMPTY {
+            self.len.fetch_add(1, Ordering::AcqRel);
+        }
+        Ok(decode(old))
+    }
+
+    /// Get the value for a given key
+    pub fn get(&self, key: K) -> Option<V> {
+        let index = self.find_slot(encode(key).ok()?)?;
+        decode(self.values[index].load(Ordering::Acquire))
+    }
+
+    pub fn contains_key(&self, key: K) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Remove a key from the map, returning the value if the key was present.
+    pub fn remove(&self, key: K) -> Option<V> {
+        let index = self.find_slot(encode(key).ok()?)?;
+        let old = self.values[index].swap(EMPTY, Ordering::AcqRel);
+        if old != EMPTY {
+            self.len.fetch_sub(1, Ordering::AcqRel);
+        }
+        decode(old)
+    }
+
+    /// Replaces the value for `key` with `new` if it's currently `current`.
+    ///
+    /// Returns the previous value on success, or the actual value (`None` if the key
+    /// isn't present) on failure. A reserved `new` always fails.
+    pub fn compare_exchange(&self, key: K, current: V, new: V) -> Result<V, Option<V>> {
+        let Some(index) = encode(key).ok().and_then(|key| self.find_slot(key)) else {
+            return Err(None);
+        };
+
+        let (Ok(current_word), Ok(new_word)) = (encode(current), encode(new)) else {
+            return Err(decode(self.values[index].load(Ordering::Acquire)));
+        };
+
+        self.values[index]
+            .compare_exchange(current_word, new_word, Ordering::AcqRel, Ordering::Acquire)
+            .map(|_| current)
+            .map_err(decode)
+    }
+
+    /// Removes every entry, including tombstones. Needs exclusive access, so no
+    /// other thread can be probing at the same time.
+    pub fn clear(&mut self) {
+        for (key, value) in self.keys.iter_mut().zip(self.values.iter_mut()) {
+            *key.get_mut() = EMPTY;
+            *value.get_mut() = EMPTY;
+        }
+        *self.claimed.get_mut() = 0;
+        *self.len.get_mut() = 0;
+    }
+
+    /// Returns the slot holding `key`, claiming an empty one if it isn't present
+    fn claim_slot(&self, key: u64) -> Result<usize, ConcurrentHashMapError> {
+        let mut index = hash_word(key) & (N - 1);
+
+        for _ in 0..N {
+            let mut slot_key = self.keys[index].load(Ordering::Acquire);
+
+            if slot_key == EMPTY {
+                if !self.reserve_claim() {
+                    return Err(ConcurrentHashMapError::Full);
+                }
+
+                match self.keys[index].compare_exchange(
+                    EMPTY,
+                    key,
+                    Ordering::AcqRel,
+                    Ordering::Acquire,
+                ) {
+                    Ok(_) => return Ok(index),
+                    Err(actual) => {
+                        // another thread got here first, give the reservation back
+                        self.claimed.fetch_sub(1, Ordering::AcqRel);
+                        slot_key = actual;
+                    }
+                }
+            }
+
+            if slot_key == key {
+                return Ok(index);
+            }
+
+            index = (index + 1) & (N - 1);
+        }
+
+        Err(ConcurrentHashMapError::Full)
+    }
+
+    /// Counts a new slot towards the load limit, returning `false` if there's no room
+    fn reserve_claim(&self) -> bool {
+        let max_len = self.max_len();
+        self.claimed
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |claimed| {
+                (claimed < max_len).then_some(claimed + 1)
+            })
+            .is_ok()
+    }
+
+    fn find_slot(&self, key: u64) -> Option<usize> {
+        let mut index = hash_word(key) & (N - 1);
+
+        for _ in 0..N {
+            match self.keys[index].load(Ordering::Acquire) {
+                EMPTY => return None,
+                slot_key if slot_key == key => return Some(index),
+                _ => {}
+            }
+
+            index = (index + 1) & (N - 1);
+        }
+
+        None
+    }
+}
+
+impl<K, V, const N: usize, const LOAD: usize> Default for ConcurrentHashMap<K, V, N, LOAD>
+where
+    K: AtomicWord,
+    V: AtomicWord,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[inline]
+fn hash_word(word: u64) -> usize {
+    fnv1a_hash(&word) as usize
+}
+
+#[inline]
+fn encode<T: AtomicWord>(value: T) -> Result<u64, ConcurrentHashMapError> {
+    match value.into_word().wrapping_add(1) {
+        EMPTY => Err(ConcurrentHashMapError::Reserved),
+        word => Ok(word),
+    }
+}
+
+#[inline]
+fn decode<T: AtomicWord>(word: u64) -> Option<T> {
+    (word != EMPTY).then(|| T::from_word(word - 1))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConcurrentHashMapError {
+    Full,
+    /// The key or value encodes to `u64::MAX`, which marks empty slots
+    Reserved,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_insert_get() {
+        let map = ConcurrentHashMap::<u32, u64, 16>::new();
+
+        assert_eq!(map.insert(42, 7), Ok(None));
+        assert_eq!(map.insert(42, 8), Ok(Some(7)));
+        assert_eq!(map.get(42), Some(8));
+        assert_eq!(map.get(43), None);
+        assert_eq!(map.len(), 1);
+    }
+
+    #[test]
+    fn test_signed_words() {
+        let map = ConcurrentHashMap::<i32, i64, 16>::new();
+
+        map.insert(-1, -5).unwrap();
+        map.insert(0, i64::MIN).unwrap();
+        assert_eq!(map.get(-1), Some(-5));
+        assert_eq!(map.get(0), Some(i64::MIN));
+
+        assert_eq!(map.insert(1, -1), Err(ConcurrentHashMapError::Reserved));
+        assert_eq!(map.get(1), None);
+    }
+
+    #[test]
+    fn test_remove_tombstone() {
+        let map = ConcurrentHashMap::<u64, u64, 8>::new();
+
+        for i in 0..6 {
+            map.insert(i, i).unwrap();
+        }
+        assert_eq!(map.remove(3), Some(3));
+        assert_eq!(map.remove(3), None);
+        assert_eq!(map.get(3), None);
+        assert_eq!(map.len(), 5);
+
+        // the removed key's slot is still claimed, so it comes back without needing room
+        assert_eq!(map.insert(6, 6), Err(ConcurrentHashMapError::Full));
+        assert_eq!(map.insert(3, 30), Ok(None));
+        assert_eq!(map.get(3), Some(30));
+    }
+
+    #[test]
+    fn test_compare_exchange() {
+        let map = ConcurrentHashMap::<u16, u32, 16>::new();
+
+        assert_eq!(map.compare_exchange(1, 0, 1), Err(None));
+        map.insert(1, 10).unwrap();
+
+        assert_eq!(map.compare_exchange(1, 10, 11), Ok(10));
+        assert_eq!(map.compare_exchange(1, 10, 12), Err(Some(11)));
+        assert_eq!(map.get(1), Some(11));
+    }
+
+    #[test]
+    fn test_clear() {
+        let mut map = ConcurrentHashMap::<u8, u8, 8>::new();
+        for i in 0..6 {
+            map.insert(i, i).unwrap();
+        }
+
+        map.clear();
+        assert!(map.is_empty());
+        assert_eq!(map.get(0), None);
+        for i in 10..16 {
+            map.insert(i, i).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_load_factor() {
+        let map = ConcurrentHashMap::<u32, u32, 8, 50>::new();
+        assert_eq!(map.max_len(), 4);
+        for key in 0..4 {
+            map.insert(key, key).unwrap();
+        }
+        assert_eq!(map.insert(4, 4), Err(ConcurrentHashMapError::Full));
+        assert_eq!(map.insert(3, 30), Ok(Some(3)));
+    }
+}
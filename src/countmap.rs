@@ -0,0 +1,283 @@
+use core::hash::Hash;
+use core::iter::FusedIterator;
+
+use crate::hashmap::{HashMap, HashMapIter};
+
+/// Fixed capacity multiset that counts occurrences of each key, backed by the open
+/// addressing [`HashMap`].
+///
+/// Keys are dropped once their count reaches zero. Capacity must be a power of 2.
+pub struct CountMap<K, const N: usize> {
+    counts: HashMap<K, usize, N>,
+    total: usize,
+}
+
+impl<K, const N: usize> CountMap<K, N>
+where
+    K: Hash + PartialEq,
+{
+    /// Panics if `N` is not a power of 2 (or is 0)
+    pub const fn new() -> Self {
+        Self {
+            counts: HashMap::new(),
+            total: 0,
+        }
+    }
+
+    #[inline]
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Number of distinct keys
+    #[inline]
+    pub const fn len(&self) -> usize {
+        self.counts.len()
+    }
+
+    #[inline]
+    pub const fn is_empty(&self) -> bool {
+        self.counts.is_empty()
+    }
+
+    /// Sum of the counts of every key
+    #[inline]
+    pub const fn total(&self) -> usize {
+        self.total
+    }
+
+    /// Counts one more occurrence of `key`, returning its new count
+    pub fn add(&mut self, key: K) -> Result<usize, CountMapError> {
+        self.add_n(key, 1)
+    }
+
+    /// Counts `n` more occurrences of `key`, returning its new count.
+    ///
+    /// Returns [`CountMapError::Overflow`] and leaves the map unchanged if the
+    /// [`total`](Self::total) would no longer fit in a `usize`.
+    pub fn add_n(&mut self, key: K, n: usize) -> Result<usize, CountMapError> {
+        if n == 0 {
+            return Ok(self.count(&key));
+        }
+
+        // every count is at most the total, so checking it covers them too
+        let total = self.total.checked_add(n).ok_or(CountMapError::Overflow)?;
+
+        let count = match self.counts.get_mut(&key) {
+            Some(count) => {
+                *count += n;
+                *count
+            }
+            None => {
+                self.counts
+                    .insert(key, n)
+                    .map_err(|_| CountMapError::Full)?;
+                n
+            }
+        };
+
+        self.total = total;
+        Ok(count)
+    }
+
+    /// Occurrences of `key`, 0 if it was never added
+    pub fn count(&self, key: &K) -> usize {
+        self.counts.get(key).copied().unwrap_or(0)
+    }
+
+    pub fn contains(&self, key: &K) -> bool {
+        self.counts.contains_key(key)
+    }
+
+    /// Takes away one occurrence of `key`, removing it when the count reaches zero.
+    ///
+    /// Returns the remaining count, or `None` if the key wasn't present.
+    pub fn decrement(&mut self, key: &K) -> Option<usize> {
+        let count = self.counts.get_mut(key)?;
+        *count -= 1;
+        let remaining = *count;
+
+        if remaining == 0 {
+            self.counts.remove(key);
+        }
+        self.total -= 1;
+        Some(remaining)
+    }
+
+    /// Removes `key` entirely, returning the count it had
+    pub fn remove(&mut self, key: &K) -> usize {
+        let count = self.counts.remove(key).unwrap_or(0);
+        self.total -= count;
+        count
+    }
+
+    pub fn clear(&mut self) {
+        self.counts.clear();
+        self.total = 0;
+    }
+
+    /// Fills `buffer` with the most common keys and their counts, highest first, and
+    /// returns how many entries were written. Ties are broken arbitrarily.
+    pub fn most_common<'a>(&'a self, buffer: &mut [Option<(&'a K, usize)>]) -> usize {
+        buffer.fill(None);
+        let mut filled = 0;
+
+        for (key, &count) in self.counts.iter() {
+            // find where this entry ranks, skipping it if the buffer is full of bigger ones
+            let mut index = filled;
+            while index > 0 && buffer[index - 1].is_some_and(|(_, other)| other < count) {
+                index -= 1;
+            }
+            if index == buffer.len() {
+                continue;
+            }
+
+            filled = (filled + 1).min(buffer.len());
+            buffer[index..filled].rotate_right(1);
+            buffer[index] = Some((key, count));
+        }
+
+        filled
+    }
+
+    pub fn iter(&self) -> CountMapIter<'_, K, N> {
+        CountMapIter {
+            inner: self.counts.iter(),
+        }
+    }
+}
+
+impl<K, const N: usize> Default for CountMap<K, N>
+where
+    K: Hash + PartialEq,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a, K, const N: usize> IntoIterator for &'a CountMap<K, N>
+where
+    K: Hash + PartialEq,
+{
+    type Item = (&'a K, usize);
+    type IntoIter = CountMapIter<'a, K, N>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Iterator over keys and their counts, see [`CountMap::iter`]
+pub struct CountMapIter<'a, K, const N: usize> {
+    inner: HashMapIter<'a, K, usize, N>,
+}
+
+impl<'a, K, const N: usize> Iterator for CountMapIter<'a, K, N> {
+    type Item = (&'a K, usize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(key, count)| (key, *count))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<'a, K, const N: usize> ExactSizeIterator for CountMapIter<'a, K, N> {
+    fn len(&self) -> usize {
+        self.inner.len()
+    }
+}
+
+impl<'a, K, const N: usize> FusedIterator for CountMapIter<'a, K, N> {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CountMapError {
+    Full,
+    /// The total count would overflow a `usize`
+    Overflow,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_add_count() {
+        let mut words = CountMap::<&str, 16>::new();
+        for word in "the cat and the hat and the bat".split(' ') {
+            words.add(word).unwrap();
+        }
+
+        assert_eq!(words.count(&"the"), 3);
+        assert_eq!(words.count(&"and"), 2);
+        assert_eq!(words.count(&"dog"), 0);
+        assert_eq!(words.len(), 5);
+        assert_eq!(words.total(), 8);
+        assert_eq!(words.add_n("cat", 4), Ok(5));
+        assert_eq!(words.add_n("dog", 0), Ok(0));
+        assert!(!words.contains(&"dog"));
+    }
+
+    #[test]
+    fn test_decrement() {
+        let mut bag = CountMap::<u32, 8>::new();
+        bag.add_n(1, 2).unwrap();
+        bag.add(2).unwrap();
+
+        assert_eq!(bag.decrement(&1), Some(1));
+        assert_eq!(bag.decrement(&1), Some(0));
+        assert!(!bag.contains(&1));
+        assert_eq!(bag.decrement(&1), None);
+        assert_eq!(bag.total(), 1);
+
+        assert_eq!(bag.remove(&2), 1);
+        assert_eq!(bag.remove(&2), 0);
+        assert!(bag.is_empty());
+        assert_eq!(bag.total(), 0);
+    }
+
+    #[test]
+    fn test_most_common() {
+        let mut bag = CountMap::<u32, 16>::new();
+        for (key, n) in [(1, 5), (2, 1), (3, 9), (4, 3), (5, 7)] {
+            bag.add_n(key, n).unwrap();
+        }
+
+        let mut top = [None; 3];
+        assert_eq!(bag.most_common(&mut top), 3);
+        assert_eq!(top, [Some((&3, 9)), Some((&5, 7)), Some((&1, 5))]);
+
+        let mut all = [None; 8];
+        assert_eq!(bag.most_common(&mut all), 5);
+        let counts: std::vec::Vec<_> = all.iter().flatten().map(|(_, count)| *count).collect();
+        assert_eq!(counts, [9, 7, 5, 3, 1]);
+
+        assert_eq!(bag.most_common(&mut []), 0);
+    }
+
+    #[test]
+    fn test_full() {
+        let mut bag = CountMap::<u32, 4>::new();
+        for key in 0..3 {
+            bag.add(key).unwrap();
+        }
+        assert_eq!(bag.add(3), Err(CountMapError::Full));
+        assert_eq!(bag.add(0), Ok(2));
+        assert_eq!(bag.total(), 4);
+    }
+
+    #[test]
+    fn test_overflow() {
+        let mut bag = CountMap::<u32, 4>::new();
+        bag.add_n(1, usize::MAX - 1).unwrap();
+        assert_eq!(bag.add(1), Ok(usize::MAX));
+        assert_eq!(bag.add(1), Err(CountMapError::Overflow));
+        assert_eq!(bag.add(2), Err(CountMapError::Overflow));
+        assert!(!bag.contains(&2));
+        assert_eq!(bag.count(&1), usize::MAX);
+        assert_eq!(bag.total(), usize::MAX);
+    }
+}
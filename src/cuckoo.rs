@@ -0,0 +1,625 @@
+use core::hash::Hash;
+use core::iter::FusedIterator;
+use core::mem::MaybeUninit;
+use core::ptr;
+
+use crate::hashmap::fnv1a_hash;
+use crate::unlikely;
+
+/// Slots per bucket, sized so a bucket of small pairs fits one cache line
+const SLOTS: usize = 4;
+/// Overflow entries kept aside when no displacement path is found
+const STASH: usize = 4;
+/// Buckets the displacement search may visit before giving up
+const MAX_SEARCH: usize = 32;
+
+const EMPTY: u8 = 0;
+const NONE: usize = usize::MAX;
+
+/// Bucketized cuckoo hash map with a worst-case O(1) lookup.
+///
+/// Every key can live in one of two buckets picked by independent halves of its
+/// hash, so a lookup reads at most two buckets (two cache lines for small pairs),
+/// plus the stash in the rare case it holds anything. Inserts search a bounded
+/// number of buckets for a chain of entries to shuffle over to their other bucket,
+/// and only move anything once a complete chain is found. If none is, the pair goes
+/// to a small stash, and once that is full too, the insert fails with nothing lost.
+///
+/// `N` is the number of buckets, which must be a power of 2. Each bucket holds 4
+/// entries.
+pub struct CuckooMap<K, V, const N: usize> {
+    buckets: [Bucket<K, V>; N],
+    stash: [Slot<K, V>; STASH],
+    stash_len: usize,
+    len: usize,
+}
+
+#[repr(align(64))]
+struct Bucket<K, V> {
+    tags: [u8; SLOTS],
+    slots: [Slot<K, V>; SLOTS],
+}
+
+struct Slot<K, V> {
+    key: MaybeUninit<K>,
+    value: MaybeUninit<V>,
+}
+
+impl<K, V> Slot<K, V> {
+    const fn new() -> Self {
+        Self {
+            key: MaybeUninit::uninit(),
+            value: MaybeUninit::uninit(),
+        }
+    }
+
+    /// # Safety
+    ///
+    /// The slot must hold a pair
+    unsafe fn read(&self) -> (K, V) {
+        unsafe { (ptr::read(self.key.as_ptr()), ptr::read(self.value.as_ptr())) }
+    }
+
+    fn write(&mut self, key: K, value: V) {
+        self.key.write(key);
+        self.value.write(value);
+    }
+}
+
+impl<K, V> Bucket<K, V> {
+    const fn new() -> Self {
+        Self {
+            tags: [EMPTY; SLOTS],
+            slots: [const { Slot::new() }; SLOTS],
+        }
+    }
+
+    fn free_slot(&self) -> Option<usize> {
+        self.tags.iter().position(|&tag| tag == EMPTY)
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Location {
+    Table(usize, usize),
+    Stash(usize),
+}
+
+/// A bucket reached by the displacement search, and how it was reached
+#[derive(Clone, Copy)]
+struct SearchNode {
+    bucket: usize,
+    /// Node whose bucket holds the entry that would move into this one
+    parent: usize,
+    /// Slot of that entry in the parent's bucket
+    slot: usize,
+}
+
+impl<K, V, const N: usize> CuckooMap<K, V, N>
+where
+    K: Hash + PartialEq,
+{
+    /// Panics if `N` is not a power of 2 (or is 0)
+    pub const fn new() -> Self {
+        assert!(N > 0, "CuckooMap size must be greater than 0");
+        assert!(N.is_power_of_two(), "CuckooMap size must be a power of 2");
+
+        Self {
+            buckets: [const { Bucket::new() }; N],
+            stash: [const { Slot::new() }; STASH],
+            stash_len: 0,
+            len: 0,
+        }
+    }
+
+    /// Inserts a key-value pair into the map, returning the old value if the key already exists
+    pub fn insert(&mut self, key: K, value: V) -> Result<Option<V>, CuckooMapError> {
+        self.try_insert(key, value)
+            .map_err(|_| CuckooMapError::Full)
+    }
+
+    /// Attempts to insert a key-value pair, returning the pair if no room can be made
+    pub fn try_insert(&mut self, key: K, value: V) -> Result<Option<V>, (K, V)> {
+        let hash = fnv1a_hash(&key);
+
+        if let Some(location) = self.find_hashed(hash, &key) {
+            let slot = self.slot_mut(location);
+            let old_value = unsafe { ptr::replace(slot.value.as_mut_ptr(), value) };
+            return Ok(Some(old_value));
+        }
+
+        let tag = tag_of(hash);
+        let (first, second) = buckets_of::<N>(hash);
+
+        for bucket in [first, second] {
+            if let Some(slot) = self.buckets[bucket].free_slot() {
+                self.place(bucket, slot, tag, key, value);
+                return Ok(None);
+            }
+        }
+
+        if let Some((bucket, slot)) = self.make_room(first, second) {
+            self.place(bucket, slot, tag, key, value);
+            return Ok(None);
+        }
+
+        if self.stash_len < STASH {
+            self.stash[self.stash_len].write(key, value);
+            self.stash_len += 1;
+            self.len += 1;
+            return Ok(None);
+        }
+
+        Err((key, value))
+    }
+
+    /// Get a reference to a value for a given key
+    pub fn get(&self, key: &K) -> Option<&V> {
+        self.find(key)
+            .map(|location| unsafe { self.slot(location).value.assume_init_ref() })
+    }
+
+    /// Get a mut reference to a value for a given key
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        self.find(key)
+            .map(|location| unsafe { self.slot_mut(location).value.assume_init_mut() })
+    }
+
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.find(key).is_some()
+    }
+
+    /// Remove a key-value pair from the map, returning the value if the key was present.
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let location = self.find(key)?;
+        self.len -= 1;
+
+        let (_, value) = match location {
+            Location::Table(bucket, slot) => {
+                self.buckets[bucket].tags[slot] = EMPTY;
+                let pair = unsafe { self.buckets[bucket].slots[slot].read() };
+                self.drain_stash();
+                pair
+            }
+            Location::Stash(index) => {
+                let pair = unsafe { self.stash[index].read() };
+                self.stash_len -= 1;
+                self.stash.swap(index, self.stash_len);
+                pair
+            }
+        };
+
+        Some(value)
+    }
+
+    fn find(&self, key: &K) -> Option<Location> {
+        self.find_hashed(fnv1a_hash(key), key)
+    }
+
+    fn find_hashed(&self, hash: u64, key: &K) -> Option<Location> {
+        let tag = tag_of(hash);
+        let (first, second) = buckets_of::<N>(hash);
+
+        for bucket in [first, second] {
+            let bucket_ref = &self.buckets[bucket];
+            for slot in 0..SLOTS {
+                if bucket_ref.tags[slot] == tag {
+                    let slot_key = unsafe { bucket_ref.slots[slot].key.assume_init_ref() };
+                    if slot_key == key {
+                        return Some(Location::Table(bucket, slot));
+                    }
+                }
+            }
+        }
+
+        if unlikely!(self.stash_len > 0) {
+            for index in 0..self.stash_len {
+                if unsafe { self.stash[index].key.assume_init_ref() } == key {
+                    return Some(Location::Stash(index));
+                }
+            }
+        }
+
+        None
+    }
+
+    /// The bucket other than `bucket` that the entry in `bucket[slot]` may live in
+    fn alternate(&self, bucket: usize, slot: usize) -> usize {
+        let key = unsafe { self.buckets[bucket].slots[slot].key.assume_init_ref() };
+        let (first, second) = buckets_of::<N>(fnv1a_hash(key));
+        if bucket == first { second } else { first }
+    }
+
+    /// Breadth-first search for a chain of entries that can each move to their other
+    /// bucket, ending at a free slot. The chain is only applied once it's complete,
+    /// so a failed search leaves the table untouched.
+    ///
+    /// Returns the slot freed up in `first` or `second`.
+    fn make_room(&mut self, first: usize, second: usize) -> Option<(usize, usize)> {
+        let root = |bucket| SearchNode {
+            bucket,
+            parent: NONE,
+            slot: NONE,
+        };
+        let mut queue = [root(first); MAX_SEARCH];
+        queue[1] = root(second);
+        let mut queued = if first == second { 1 } else { 2 };
+        let mut head = 0;
+
+        while head < queued {
+            let node = queue[head];
+
+            for slot in 0..SLOTS {
+                let target = self.alternate(node.bucket, slot);
+
+                if let Some(free) = self.buckets[target].free_slot() {
+                    return Some(self.shift_chain(&queue, head, slot, target, free));
+                }
+
+                if queued < MAX_SEARCH && !Self::on_path(&queue, head, target) {
+                    queue[queued] = SearchNode {
+                        bucket: target,
+                        parent: head,
+                        slot,
+                    };
+                    queued += 1;
+                }
+            }
+
+            head += 1;
+        }
+
+        None
+    }
+
+    /// Whether `bucket` already appears on the chain leading to `queue[node]`, where
+    /// moving through it twice would clobber an entry
+    fn on_path(queue: &[SearchNode], mut node: usize, bucket: usize) -> bool {
+        while node != NONE {
+            if queue[node].bucket == bucket {
+                return true;
+            }
+            node = queue[node].parent;
+        }
+        false
+    }
+
+    /// Moves every entry along the chain ending at `queue[node]` one step, starting from
+    /// the free slot at the far end, and returns the slot left free at the root
+    fn shift_chain(
+        &mut self,
+        queue: &[SearchNode],
+        mut node: usize,
+        mut slot: usize,
+        mut target: usize,
+        mut free: usize,
+    ) -> (usize, usize) {
+        loop {
+            let bucket = queue[node].bucket;
+            self.move_slot(bucket, slot, target, free);
+
+            if queue[node].parent == NONE {
+                return (bucket, slot);
+            }
+
+            target = bucket;
+            free = slot;
+            slot = queue[node].slot;
+            node = queue[node].parent;
+        }
+    }
+
+    fn move_slot(&mut self, from: usize, from_slot: usize, to: usize, to_slot: usize) {
+        let tag = self.buckets[from].tags[from_slot];
+        let (key, value) = unsafe { self.buckets[from].slots[from_slot].read() };
+        self.buckets[from].tags[from_slot] = EMPTY;
+        self.place(to, to_slot, tag, key, value);
+        self.len -= 1;
+    }
+
+    fn place(&mut self, bucket: usize, slot: usize, tag: u8, key: K, value: V) {
+        self.buckets[bucket].tags[slot] = tag;
+        self.buckets[bucket].slots[slot].write(key, value);
+        self.len += 1;
+    }
+
+    /// Moves stashed entries back into the table where a removal made room
+    fn drain_stash(&mut self) {
+        let mut index = 0;
+        while index < self.stash_len {
+            let key = unsafe { self.stash[index].key.assume_init_ref() };
+            let hash = fnv1a_hash(key);
+            let (first, second) = buckets_of::<N>(hash);
+
+            let free = [first, second]
+                .into_iter()
+                .find_map(|bucket| Some((bucket, self.buckets[bucket].free_slot()?)));
+
+            match free {
+                Some((bucket, slot)) => {
+                    let (key, value) = unsafe { self.stash[index].read() };
+                    self.stash_len -= 1;
+                    self.stash.swap(index, self.stash_len);
+                    self.buckets[bucket].tags[slot] = tag_of(hash);
+                    self.buckets[bucket].slots[slot].write(key, value);
+                }
+                None => index += 1,
+            }
+        }
+    }
+}
+
+impl<K, V, const N: usize> CuckooMap<K, V, N> {
+    /// Number of entries the table holds, not counting the stash
+    #[inline]
+    pub const fn capacity(&self) -> usize {
+        N * SLOTS
+    }
+
+    #[inline]
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    #[inline]
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn clear(&mut self) {
+        self.drop_occupied();
+        for bucket in self.buckets.iter_mut() {
+            bucket.tags = [EMPTY; SLOTS];
+        }
+        self.stash_len = 0;
+        self.len = 0;
+    }
+
+    pub fn iter(&self) -> CuckooMapIter<'_, K, V, N> {
+        CuckooMapIter {
+            map: self,
+            index: 0,
+            remaining: self.len,
+        }
+    }
+
+    fn slot(&self, location: Location) -> &Slot<K, V> {
+        match location {
+            Location::Table(bucket, slot) => &self.buckets[bucket].slots[slot],
+            Location::Stash(index) => &self.stash[index],
+        }
+    }
+
+    fn slot_mut(&mut self, location: Location) -> &mut Slot<K, V> {
+        match location {
+            Location::Table(bucket, slot) => &mut self.buckets[bucket].slots[slot],
+            Location::Stash(index) => &mut self.stash[index],
+        }
+    }
+
+    /// Drops every stored key and value, leaving the tags untouched
+    fn drop_occupied(&mut self) {
+        for bucket in self.buckets.iter_mut() {
+            for slot in 0..SLOTS {
+                if bucket.tags[slot] != EMPTY {
+                    bucket.tags[slot] = EMPTY;
+                    unsafe {
+                        bucket.slots[slot].key.assume_init_drop();
+                        bucket.slots[slot].value.assume_init_drop();
+                    }
+                }
+            }
+        }
+
+        let stashed = self.stash_len;
+        self.stash_len = 0;
+        for slot in &mut self.stash[..stashed] {
+            unsafe {
+                slot.key.assume_init_drop();
+                slot.value.assume_init_drop();
+            }
+        }
+    }
+}
+
+#[inline]
+fn tag_of(hash: u64) -> u8 {
+    (hash >> 56) as u8 | 0x80
+}
+
+/// Two candidate buckets from independent halves of the hash
+#[inline]
+fn buckets_of<const N: usize>(hash: u64) -> (usize, usize) {
+    let first = hash as usize & (N - 1);
+    let second = (hash >> 32) as usize & (N - 1);
+    if second == first {
+        (first, (first + 1) & (N - 1))
+    } else {
+        (first, second)
+    }
+}
+
+impl<K, V, const N: usize> Default for CuckooMap<K, V, N>
+where
+    K: Hash + PartialEq,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, V, const N: usize> Drop for CuckooMap<K, V, N> {
+    fn drop(&mut self) {
+        self.drop_occupied();
+    }
+}
+
+impl<'a, K, V, const N: usize> IntoIterator for &'a CuckooMap<K, V, N> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = CuckooMapIter<'a, K, V, N>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Iterator over the table, then the stash
+pub struct CuckooMapIter<'a, K, V, const N: usize> {
+    map: &'a CuckooMap<K, V, N>,
+    index: usize,
+    remaining: usize,
+}
+
+impl<'a, K, V, const N: usize> Iterator for CuckooMapIter<'a, K, V, N> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.remaining > 0 {
+            let index = self.index;
+            self.index += 1;
+
+            let slot = if index < N * SLOTS {
+                let bucket = &self.map.buckets[index / SLOTS];
+                if bucket.tags[index % SLOTS] == EMPTY {
+                    continue;
+                }
+                &bucket.slots[index % SLOTS]
+            } else {
+                &self.map.stash[index - N * SLOTS]
+            };
+
+            self.remaining -= 1;
+            return Some(unsafe { (slot.key.assume_init_ref(), slot.value.assume_init_ref()) });
+        }
+
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'a, K, V, const N: usize> ExactSizeIterator for CuckooMapIter<'a, K, V, N> {
+    fn len(&self) -> usize {
+        self.remaining
+    }
+}
+
+impl<'a, K, V, const N: usize> FusedIterator for CuckooMapIter<'a, K, V, N> {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CuckooMapError {
+    Full,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::rc::Rc;
+    use std::string::{String, ToString};
+
+    #[test]
+    fn test_insert_get() {
+        let mut map = CuckooMap::<u32, String, 8>::new();
+
+        assert_eq!(map.insert(1, "one".to_string()), Ok(None));
+        assert_eq!(map.insert(2, "two".to_string()), Ok(None));
+        assert_eq!(map.get(&1), Some(&"one".to_string()));
+        assert_eq!(map.get(&3), None);
+
+        assert_eq!(
+            map.insert(1, "uno".to_string()),
+            Ok(Some("one".to_string()))
+        );
+        map.get_mut(&2).unwrap().push('!');
+        assert_eq!(map.get(&2), Some(&"two!".to_string()));
+        assert_eq!(map.len(), 2);
+    }
+
+    #[test]
+    fn test_fill_until_full() {
+        let mut map = CuckooMap::<u32, u32, 16>::new();
+
+        let mut inserted = 0;
+        while map.insert(inserted, inserted * 2).is_ok() {
+            inserted += 1;
+        }
+
+        // cuckoo tables with 4-way buckets load well past 90%
+        assert!(inserted as usize > map.capacity() * 9 / 10);
+        assert_eq!(map.len(), inserted as usize);
+
+        // a failed insert hands the pair back and loses nothing
+        assert_eq!(map.try_insert(1000, 7), Err((1000, 7)));
+        for i in 0..inserted {
+            assert_eq!(map.get(&i), Some(&(i * 2)));
+        }
+        assert_eq!(map.iter().count(), inserted as usize);
+
+        // replacing still works when full
+        assert_eq!(map.insert(0, 1), Ok(Some(0)));
+    }
+
+    #[test]
+    fn test_remove_and_stash() {
+        let mut map = CuckooMap::<u32, u32, 4>::new();
+        let mut inserted = 0;
+        while map.insert(inserted, inserted).is_ok() {
+            inserted += 1;
+        }
+        assert!(map.stash_len > 0);
+
+        for i in 0..inserted {
+            assert_eq!(map.remove(&i), Some(i));
+            assert_eq!(map.get(&i), None);
+            for j in i + 1..inserted {
+                assert_eq!(map.get(&j), Some(&j));
+            }
+        }
+        assert!(map.is_empty());
+        assert_eq!(map.stash_len, 0);
+    }
+
+    #[test]
+    fn test_stash_drains_on_remove() {
+        let mut map = CuckooMap::<u32, u32, 2>::new();
+        let mut inserted = 0;
+        while map.insert(inserted, inserted).is_ok() {
+            inserted += 1;
+        }
+        assert_eq!(map.stash_len, STASH);
+
+        // freeing table slots lets stashed entries move back
+        for i in 0..inserted {
+            if map.stash_len == 0 {
+                break;
+            }
+            map.remove(&i);
+        }
+        assert_eq!(map.stash_len, 0);
+        assert_eq!(map.iter().count(), map.len());
+    }
+
+    #[test]
+    fn test_drop() {
+        let tracker = Rc::new(());
+        {
+            let mut map = CuckooMap::<u32, Rc<()>, 2>::new();
+            let mut i = 0;
+            while map.insert(i, tracker.clone()).is_ok() {
+                i += 1;
+            }
+            assert_eq!(Rc::strong_count(&tracker), map.len() + 1);
+
+            map.remove(&0);
+            assert_eq!(Rc::strong_count(&tracker), map.len() + 1);
+
+            map.clear();
+            assert_eq!(Rc::strong_count(&tracker), 1);
+
+            map.insert(1, tracker.clone()).unwrap();
+        }
+        assert_eq!(Rc::strong_count(&tracker), 1);
+    }
+}
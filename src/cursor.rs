@@ -0,0 +1,329 @@
+use crate::fixedvec::FixedVec;
+
+/// Reads and writes bytes at a position within a [`FixedVec<u8, N>`], for
+/// serializing packets into a fixed-size buffer.
+///
+/// Writes overwrite the bytes at the position and grow the buffer past its end.
+/// [`write_all`](Self::write_all), [`read_exact`](Self::read_exact) and the typed
+/// `put_*`/`get_*` helpers either move the whole value or fail without changing
+/// anything.
+pub struct FixedCursor<const N: usize> {
+    buf: FixedVec<u8, N>,
+    pos: usize,
+}
+
+impl<const N: usize> FixedCursor<N> {
+    /// Cursor over an empty buffer
+    pub const fn new() -> Self {
+        Self {
+            buf: FixedVec::new(),
+            pos: 0,
+        }
+    }
+
+    #[inline]
+    pub const fn position(&self) -> usize {
+        self.pos
+    }
+
+    /// Moves the cursor, panicking if `pos` is past the end of the buffer
+    pub fn set_position(&mut self, pos: usize) {
+        assert!(pos <= self.buf.len(), "Position {} out of bounds", pos);
+        self.pos = pos;
+    }
+
+    /// Number of bytes between the position and the end of the buffer
+    #[inline]
+    pub const fn remaining(&self) -> usize {
+        self.buf.len() - self.pos
+    }
+
+    pub fn get_ref(&self) -> &FixedVec<u8, N> {
+        &self.buf
+    }
+
+    pub fn into_inner(self) -> FixedVec<u8, N> {
+        self.buf
+    }
+
+    /// Writes all of `bytes` at the position, returning an error (with nothing
+    /// written) if they don't fit
+    pub fn write_all(&mut self, bytes: &[u8]) -> Result<(), FixedCursorError> {
+        if bytes.len() > N - self.pos {
+            return Err(FixedCursorError::Full);
+        }
+
+        self.write_some(bytes);
+        Ok(())
+    }
+
+    /// Fills `buf` from the position, returning an error (with nothing read) if
+    /// there aren't enough bytes left
+    pub fn read_exact(&mut self, buf: &mut [u8]) -> Result<(), FixedCursorError> {
+        if buf.len() > self.remaining() {
+            return Err(FixedCursorError::UnexpectedEof);
+        }
+
+        self.read_some(buf);
+        Ok(())
+    }
+
+    /// Writes as much of `bytes` as fits, returning how many were written
+    fn write_some(&mut self, bytes: &[u8]) -> usize {
+        let count = bytes.len().min(N - self.pos);
+        let (overwrite, append) = bytes[..count].split_at(count.min(self.remaining()));
+
+        self.buf[self.pos..][..overwrite.len()].copy_from_slice(overwrite);
+        let appended = self.buf.extend_from_slice(append);
+        debug_assert!(appended.is_ok());
+
+        self.pos += count;
+        count
+    }
+
+    /// Reads as many bytes as are left into `buf`, returning how many were read
+    fn read_some(&mut self, buf: &mut [u8]) -> usize {
+        let count = buf.len().min(self.remaining());
+        buf[..count].copy_from_slice(&self.buf[self.pos..][..count]);
+        self.pos += count;
+        count
+    }
+
+    pub fn put_u8(&mut self, value: u8) -> Result<(), FixedCursorError> {
+        self.write_all(&[value])
+    }
+
+    pub fn put_i8(&mut self, value: i8) -> Result<(), FixedCursorError> {
+        self.write_all(&value.to_le_bytes())
+    }
+
+    pub fn get_u8(&mut self) -> Result<u8, FixedCursorError> {
+        let mut bytes = [0; 1];
+        self.read_exact(&mut bytes)?;
+        Ok(bytes[0])
+    }
+
+    pub fn get_i8(&mut self) -> Result<i8, FixedCursorError> {
+        self.get_u8().map(|byte| byte as i8)
+    }
+}
+
+macro_rules! impl_put_get {
+    ($($ty:ty => $put_le:ident, $put_be:ident, $get_le:ident, $get_be:ident;)*) => {
+        impl<const N: usize> FixedCursor<N> {
+            $(
+                #[doc = concat!("Writes a little-endian `", stringify!($ty), "`")]
+                pub fn $put_le(&mut self, value: $ty) -> Result<(), FixedCursorError> {
+                    self.write_all(&value.to_le_bytes())
+                }
+
+                #[doc = concat!("Writes a big-endian `", stringify!($ty), "`")]
+                pub fn $put_be(&mut self, value: $ty) -> Result<(), FixedCursorError> {
+                    self.write_all(&value.to_be_bytes())
+                }
+
+                #[doc = concat!("Reads a little-endian `", stringify!($ty), "`")]
+                pub fn $get_le(&mut self) -> Result<$ty, FixedCursorError> {
+                    let mut bytes = [0; size_of::<$ty>()];
+                    self.read_exact(&mut bytes)?;
+                    Ok(<$ty>::from_le_bytes(bytes))
+                }
+
+                #[doc = concat!("Reads a big-endian `", stringify!($ty), "`")]
+                pub fn $get_be(&mut self) -> Result<$ty, FixedCursorError> {
+                    let mut bytes = [0; size_of::<$ty>()];
+                    self.read_exact(&mut bytes)?;
+                    Ok(<$ty>::from_be_bytes(bytes))
+                }
+            )*
+        }
+    };
+}
+
+impl_put_get! {
+    u16 => put_u16_le, put_u16_be, get_u16_le, get_u16_be;
+    u32 => put_u32_le, put_u32_be, get_u32_le, get_u32_be;
+    u64 => put_u64_le, put_u64_be, get_u64_le, get_u64_be;
+    u128 => put_u128_le, put_u128_be, get_u128_le, get_u128_be;
+    i16 => put_i16_le, put_i16_be, get_i16_le, get_i16_be;
+    i32 => put_i32_le, put_i32_be, get_i32_le, get_i32_be;
+    i64 => put_i64_le, put_i64_be, get_i64_le, get_i64_be;
+    i128 => put_i128_le, put_i128_be, get_i128_le, get_i128_be;
+    f32 => put_f32_le, put_f32_be, get_f32_le, get_f32_be;
+    f64 => put_f64_le, put_f64_be, get_f64_le, get_f64_be;
+}
+
+impl<const N: usize> Default for FixedCursor<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Starts at the beginning of the buffer, so its contents can be read back
+impl<const N: usize> From<FixedVec<u8, N>> for FixedCursor<N> {
+    fn from(buf: FixedVec<u8, N>) -> Self {
+        Self { buf, pos: 0 }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<const N: usize> std::io::Read for FixedCursor<N> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        Ok(self.read_some(buf))
+    }
+}
+
+/// Writes stop short once the buffer is full, so `write_all` fails with
+/// [`WriteZero`](std::io::ErrorKind::WriteZero)
+#[cfg(feature = "std")]
+impl<const N: usize> std::io::Write for FixedCursor<N> {
+    fn write(&mut self, bytes: &[u8]) -> std::io::Result<usize> {
+        Ok(self.write_some(bytes))
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(feature = "embedded-io")]
+impl<const N: usize> embedded_io::ErrorType for FixedCursor<N> {
+    type Error = FixedCursorError;
+}
+
+#[cfg(feature = "embedded-io")]
+impl<const N: usize> embedded_io::Read for FixedCursor<N> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        Ok(self.read_some(buf))
+    }
+}
+
+#[cfg(feature = "embedded-io")]
+impl<const N: usize> embedded_io::Write for FixedCursor<N> {
+    fn write(&mut self, bytes: &[u8]) -> Result<usize, Self::Error> {
+        match self.write_some(bytes) {
+            0 if !bytes.is_empty() => Err(FixedCursorError::Full),
+            count => Ok(count),
+        }
+    }
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FixedCursorError {
+    Full,
+    UnexpectedEof,
+}
+
+#[cfg(feature = "embedded-io")]
+impl embedded_io::Error for FixedCursorError {
+    fn kind(&self) -> embedded_io::ErrorKind {
+        match self {
+            FixedCursorError::Full => embedded_io::ErrorKind::WriteZero,
+            FixedCursorError::UnexpectedEof => embedded_io::ErrorKind::Other,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_put_get() {
+        let mut cursor = FixedCursor::<32>::new();
+        cursor.put_u8(0xab).unwrap();
+        cursor.put_u16_be(0x0102).unwrap();
+        cursor.put_u32_le(0x0304_0506).unwrap();
+        cursor.put_i16_le(-2).unwrap();
+        cursor.put_f32_be(1.5).unwrap();
+        cursor.put_f64_le(-0.25).unwrap();
+        assert_eq!(cursor.position(), 21);
+        assert_eq!(
+            cursor.get_ref()[..7],
+            [0xab, 0x01, 0x02, 0x06, 0x05, 0x04, 0x03]
+        );
+
+        let mut cursor = FixedCursor::from(cursor.into_inner());
+        assert_eq!(cursor.get_u8(), Ok(0xab));
+        assert_eq!(cursor.get_u16_be(), Ok(0x0102));
+        assert_eq!(cursor.get_u32_le(), Ok(0x0304_0506));
+        assert_eq!(cursor.get_i16_le(), Ok(-2));
+        assert_eq!(cursor.get_f32_be(), Ok(1.5));
+        assert_eq!(cursor.get_f64_le(), Ok(-0.25));
+        assert_eq!(cursor.get_u8(), Err(FixedCursorError::UnexpectedEof));
+    }
+
+    #[test]
+    fn test_all_or_nothing() {
+        let mut cursor = FixedCursor::<6>::new();
+        cursor.put_u32_be(7).unwrap();
+        assert_eq!(cursor.put_u32_be(8), Err(FixedCursorError::Full));
+        assert_eq!(cursor.get_ref().len(), 4);
+        cursor.put_u16_le(9).unwrap();
+
+        cursor.set_position(2);
+        let mut buf = [0; 8];
+        assert_eq!(
+            cursor.read_exact(&mut buf),
+            Err(FixedCursorError::UnexpectedEof)
+        );
+        assert_eq!(cursor.position(), 2);
+        assert_eq!(cursor.get_u16_be(), Ok(7));
+        assert_eq!(cursor.remaining(), 2);
+    }
+
+    #[test]
+    fn test_overwrite() {
+        let mut cursor = FixedCursor::<5>::from(crate::fixedvec![1, 2, 3]);
+        cursor.set_position(1);
+        cursor.write_all(&[8, 9, 10]).unwrap();
+        assert_eq!(cursor.get_ref(), &[1, 8, 9, 10]);
+
+        // a header filled in after the body
+        cursor.set_position(0);
+        cursor.put_u8(4).unwrap();
+        assert_eq!(cursor.into_inner(), [4, 8, 9, 10]);
+    }
+
+    #[test]
+    #[should_panic(expected = "out of bounds")]
+    fn test_set_position_past_end() {
+        FixedCursor::<4>::new().set_position(1);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_std_io() {
+        use std::io::{Read, Write};
+
+        let mut cursor = FixedCursor::<4>::new();
+        assert_eq!(cursor.write(&[1, 2, 3, 4, 5]).unwrap(), 4);
+        let error = Write::write_all(&mut cursor, &[6]).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::WriteZero);
+
+        cursor.set_position(1);
+        let mut rest = std::vec::Vec::new();
+        cursor.read_to_end(&mut rest).unwrap();
+        assert_eq!(rest, [2, 3, 4]);
+    }
+
+    #[cfg(feature = "embedded-io")]
+    #[test]
+    fn test_embedded_io() {
+        use embedded_io::{Read, Write};
+
+        let mut cursor = FixedCursor::<4>::new();
+        Write::write_all(&mut cursor, &[1, 2, 3]).unwrap();
+        assert_eq!(Write::write(&mut cursor, &[4, 5]), Ok(1));
+        assert_eq!(Write::write(&mut cursor, &[6]), Err(FixedCursorError::Full));
+
+        cursor.set_position(0);
+        let mut buf = [0; 4];
+        Read::read_exact(&mut cursor, &mut buf).unwrap();
+        assert_eq!(buf, [1, 2, 3, 4]);
+    }
+}
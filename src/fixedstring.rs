@@ -0,0 +1,363 @@
+use core::borrow::Borrow;
+use core::cmp::Ordering;
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::ops::{Deref, DerefMut};
+use core::str;
+
+use crate::fixedvec::{FixedVec, Length};
+
+/// UTF-8 string with a fixed capacity of `N` bytes and inline storage.
+///
+/// The length is stored as `L`, see [`Length`].
+pub struct FixedString<const N: usize, L: Length = usize> {
+    bytes: FixedVec<u8, N, L>,
+}
+
+impl<const N: usize, L: Length> FixedString<N, L> {
+    pub const fn new() -> Self {
+        Self {
+            bytes: FixedVec::new(),
+        }
+    }
+
+    /// Capacity in bytes
+    #[inline]
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Length in bytes
+    #[inline]
+    pub const fn len(&self) -> usize {
+        self.bytes.len()
+    }
+
+    #[inline]
+    pub const fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    #[inline]
+    pub const fn is_full(&self) -> bool {
+        self.bytes.is_full()
+    }
+
+    #[inline]
+    pub const fn remaining_capacity(&self) -> usize {
+        self.bytes.remaining_capacity()
+    }
+
+    pub fn as_str(&self) -> &str {
+        unsafe { str::from_utf8_unchecked(self.bytes.as_slice()) }
+    }
+
+    pub fn as_mut_str(&mut self) -> &mut str {
+        unsafe { str::from_utf8_unchecked_mut(self.bytes.as_mut_slice()) }
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        self.bytes.as_slice()
+    }
+
+    /// Appends a string slice, returning an error (with the string unchanged) if it
+    /// doesn't fit
+    pub fn push_str(&mut self, s: &str) -> Result<(), FixedStringError> {
+        self.bytes
+            .extend_from_slice(s.as_bytes())
+            .map_err(|_| FixedStringError::Full)
+    }
+
+    /// Appends as much of a string slice as fits without splitting a character,
+    /// returning `false` if any of it was cut off
+    pub fn push_str_truncated(&mut self, s: &str) -> bool {
+        let mut end = s.len().min(self.remaining_capacity());
+        while !s.is_char_boundary(end) {
+            end -= 1;
+        }
+
+        let fits = self.bytes.extend_from_slice(&s.as_bytes()[..end]).is_ok();
+        debug_assert!(fits);
+        end == s.len()
+    }
+
+    /// Appends a character, returning an error if its encoding doesn't fit
+    pub fn push(&mut self, c: char) -> Result<(), FixedStringError> {
+        self.push_str(c.encode_utf8(&mut [0; 4]))
+    }
+
+    /// Removes and returns the last character, returning `None` if empty
+    pub fn pop(&mut self) -> Option<char> {
+        let c = self.as_str().chars().next_back()?;
+        self.bytes.truncate(self.len() - c.len_utf8());
+        Some(c)
+    }
+
+    /// Shortens the string to `new_len` bytes, panicking if that isn't a character
+    /// boundary
+    pub fn truncate(&mut self, new_len: usize) {
+        if new_len < self.len() {
+            assert!(
+                self.as_str().is_char_boundary(new_len),
+                "Length {} is not a char boundary",
+                new_len
+            );
+            self.bytes.truncate(new_len);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.bytes.clear();
+    }
+
+    /// Writer whose [`fmt::Write`] impl cuts off whatever doesn't fit instead of failing
+    pub fn truncating(&mut self) -> FixedStringTruncating<'_, N, L> {
+        FixedStringTruncating {
+            string: self,
+            truncated: false,
+        }
+    }
+}
+
+/// Fails with [`fmt::Error`] on the first piece that doesn't fit, leaving that piece
+/// out. Pieces of the same `write!` that came before it stay written.
+impl<const N: usize, L: Length> fmt::Write for FixedString<N, L> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.push_str(s).map_err(|_| fmt::Error)
+    }
+}
+
+/// Writer returned by [`FixedString::truncating`]
+pub struct FixedStringTruncating<'a, const N: usize, L: Length = usize> {
+    string: &'a mut FixedString<N, L>,
+    truncated: bool,
+}
+
+impl<'a, const N: usize, L: Length> FixedStringTruncating<'a, N, L> {
+    /// Whether any write so far was cut off
+    pub fn truncated(&self) -> bool {
+        self.truncated
+    }
+}
+
+impl<'a, const N: usize, L: Length> fmt::Write for FixedStringTruncating<'a, N, L> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        if !self.truncated && !self.string.push_str_truncated(s) {
+            self.truncated = true;
+        }
+        Ok(())
+    }
+}
+
+impl<const N: usize, L: Length> Default for FixedString<N, L> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize, L: Length> Clone for FixedString<N, L> {
+    fn clone(&self) -> Self {
+        Self {
+            bytes: self.bytes.clone(),
+        }
+    }
+}
+
+impl<const N: usize, L: Length> Deref for FixedString<N, L> {
+    type Target = str;
+
+    fn deref(&self) -> &Self::Target {
+        self.as_str()
+    }
+}
+
+impl<const N: usize, L: Length> DerefMut for FixedString<N, L> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.as_mut_str()
+    }
+}
+
+impl<const N: usize, L: Length> AsRef<str> for FixedString<N, L> {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl<const N: usize, L: Length> AsRef<[u8]> for FixedString<N, L> {
+    fn as_ref(&self) -> &[u8] {
+        self.as_bytes()
+    }
+}
+
+impl<const N: usize, L: Length> Borrow<str> for FixedString<N, L> {
+    fn borrow(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl<const N: usize, L: Length> TryFrom<&str> for FixedString<N, L> {
+    type Error = FixedStringError;
+
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        let mut string = Self::new();
+        string.push_str(s)?;
+        Ok(string)
+    }
+}
+
+impl<const N: usize, L: Length> fmt::Display for FixedString<N, L> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self.as_str(), f)
+    }
+}
+
+impl<const N: usize, L: Length> fmt::Debug for FixedString<N, L> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl<const N: usize, const M: usize, L: Length, K: Length> PartialEq<FixedString<M, K>>
+    for FixedString<N, L>
+{
+    fn eq(&self, other: &FixedString<M, K>) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+impl<const N: usize, L: Length> PartialEq<str> for FixedString<N, L> {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl<const N: usize, L: Length> PartialEq<&str> for FixedString<N, L> {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl<const N: usize, L: Length> Eq for FixedString<N, L> {}
+
+impl<const N: usize, const M: usize, L: Length, K: Length> PartialOrd<FixedString<M, K>>
+    for FixedString<N, L>
+{
+    fn partial_cmp(&self, other: &FixedString<M, K>) -> Option<Ordering> {
+        Some(self.as_str().cmp(other.as_str()))
+    }
+}
+
+impl<const N: usize, L: Length> Ord for FixedString<N, L> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.as_str().cmp(other.as_str())
+    }
+}
+
+/// Hashes the same as the `str` it derefs to, as [`Borrow<str>`] requires
+impl<const N: usize, L: Length> Hash for FixedString<N, L> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_str().hash(state);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FixedStringError {
+    Full,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::HashMap;
+    use core::fmt::Write;
+
+    #[test]
+    fn test_push() {
+        let mut s = FixedString::<8>::new();
+        s.push_str("héllo").unwrap();
+        assert_eq!(s.len(), 6);
+        s.push('!').unwrap();
+        assert_eq!(s, "héllo!");
+
+        assert_eq!(s.push('é'), Err(FixedStringError::Full));
+        assert_eq!(s.push_str("ab"), Err(FixedStringError::Full));
+        assert_eq!(s, "héllo!");
+        s.push('a').unwrap();
+        assert!(s.is_full());
+
+        assert_eq!(s.pop(), Some('a'));
+        assert_eq!(s.pop(), Some('!'));
+        s.truncate(3);
+        assert_eq!(s, "hé");
+
+        s.make_ascii_uppercase();
+        assert_eq!(s, "Hé");
+        s.clear();
+        assert_eq!(s.pop(), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "not a char boundary")]
+    fn test_truncate_mid_char() {
+        let mut s = FixedString::<8>::try_from("héllo").unwrap();
+        s.truncate(2);
+    }
+
+    #[test]
+    fn test_fmt_write() {
+        let mut s = FixedString::<16>::new();
+        let (n, word) = (12, "ab");
+        write!(s, "{n}-{word}").unwrap();
+        assert_eq!(s, "12-ab");
+
+        // the piece that doesn't fit is left out, but earlier pieces of the same
+        // `write!` have already been written
+        assert!(write!(s, "{n} is too long").is_err());
+        assert_eq!(s, "12-ab12");
+
+        let mut short = FixedString::<6>::new();
+        let mut writer = short.truncating();
+        let (first, second) = ("añ", "bcd");
+        write!(writer, "{first} {second}").unwrap();
+        assert!(writer.truncated());
+        // the 'ñ' fits, so the cut happens inside the second word
+        assert_eq!(short, "añ bc");
+
+        let mut tight = FixedString::<2>::new();
+        let mut writer = tight.truncating();
+        write!(writer, "añ").unwrap();
+        assert!(writer.truncated());
+        assert_eq!(tight, "a");
+    }
+
+    #[test]
+    fn test_traits() {
+        let a = FixedString::<8>::try_from("apple").unwrap();
+        let b = FixedString::<16>::try_from("banana").unwrap();
+        assert!(FixedString::<4>::try_from("apple").is_err());
+
+        assert!(a < b);
+        assert_eq!(a.clone(), a);
+        assert_eq!(std::format!("{a}/{a:?}"), "apple/\"apple\"");
+        assert!(a.starts_with("app"));
+
+        let mut map = HashMap::<FixedString<8>, u32, 8>::new();
+        map.insert(a, 1).unwrap();
+        assert_eq!(map.get(&FixedString::try_from("apple").unwrap()), Some(&1));
+
+        let mut set = std::collections::BTreeSet::new();
+        set.insert(FixedString::<8>::try_from("pear").unwrap());
+        assert!(set.contains("pear"));
+    }
+
+    #[test]
+    fn test_length_type() {
+        assert_eq!(size_of::<FixedString<15, u8>>(), 16);
+
+        let mut name = FixedString::<15, u8>::new();
+        name.push_str("sakura").unwrap();
+        assert_eq!(name, FixedString::<8>::try_from("sakura").unwrap());
+        assert!(!name.push_str_truncated("-no-hana-chiru"));
+        assert_eq!(name, "sakura-no-hana-");
+    }
+}
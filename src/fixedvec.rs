@@ -1,19 +1,33 @@
-use core::mem::MaybeUninit;
-use core::ops::{Index, IndexMut};
+use core::borrow::{Borrow, BorrowMut};
+use core::cmp::Ordering;
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::iter::{self, Chain, FusedIterator, Once};
+use core::marker::PhantomData;
+use core::mem::{self, ManuallyDrop, MaybeUninit};
+use core::ops::{Bound, Deref, DerefMut, Index, IndexMut, RangeBounds};
 use core::ptr;
-use core::slice;
+use core::slice::{self, SliceIndex};
+use core::sync::atomic::{self, AtomicU8, AtomicU16, AtomicU32, AtomicUsize};
 
-/// Similar interface to `Vec`, but with a fixed capacity and inline storage
-pub struct FixedVec<T, const N: usize> {
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+/// Similar interface to `Vec`, but with a fixed capacity and inline storage.
+///
+/// The length is stored as `L`, see [`Length`].
+pub struct FixedVec<T, const N: usize, L: Length = usize> {
     data: [MaybeUninit<T>; N],
-    len: usize,
+    len: L,
 }
 
-impl<T, const N: usize> FixedVec<T, N> {
+impl<T, const N: usize, L: Length> FixedVec<T, N, L> {
     pub const fn new() -> Self {
+        let () = LengthFits::<L, N>::CHECK;
+
         Self {
             data: unsafe { MaybeUninit::uninit().assume_init() },
-            len: 0,
+            len: length_from_usize(0),
         }
     }
 
@@ -24,65 +38,89 @@ impl<T, const N: usize> FixedVec<T, N> {
 
     #[inline]
     pub const fn len(&self) -> usize {
-        self.len
+        length_to_usize(&self.len)
+    }
+
+    #[inline]
+    const fn set_len(&mut self, len: usize) {
+        self.len = length_from_usize(len);
     }
 
     #[inline]
     pub const fn is_empty(&self) -> bool {
-        self.len == 0
+        self.len() == 0
     }
 
     #[inline]
     pub const fn is_full(&self) -> bool {
-        self.len == N
+        self.len() == N
     }
 
     #[inline]
     pub const fn remaining_capacity(&self) -> usize {
-        N - self.len
+        N - self.len()
+    }
+
+    /// Builds a vector holding every element of `array`, usually through
+    /// [`fixedvec!`](crate::fixedvec).
+    ///
+    /// Fails to compile if the array is longer than the vector's capacity
+    pub const fn from_array<const M: usize>(array: [T; M]) -> Self {
+        let () = Fits::<M, N>::CHECK;
+
+        let mut vec = Self::new();
+        unsafe {
+            ptr::copy_nonoverlapping(
+                (&raw const array).cast::<T>(),
+                vec.data.as_mut_ptr().cast::<T>(),
+                M,
+            );
+        }
+        mem::forget(array);
+        vec.set_len(M);
+        vec
     }
 
     /// Pushes an element to the end of the vector, returning an error if full
-    pub fn push(&mut self, value: T) -> Result<(), FixedVecError> {
-        if self.len >= N {
+    pub const fn push(&mut self, value: T) -> Result<(), FixedVecError> {
+        if self.len() >= N {
+            crate::discard(value);
             return Err(FixedVecError::Full);
         }
 
         unsafe {
-            ptr::write(self.data[self.len].as_mut_ptr(), value);
+            ptr::write(self.data[self.len()].as_mut_ptr(), value);
         }
-        self.len += 1;
+        self.set_len(self.len() + 1);
         Ok(())
     }
 
     /// Removes and returns the last element, returning `None` if empty
     pub fn pop(&mut self) -> Option<T> {
-        if self.len == 0 {
+        if self.is_empty() {
             None
         } else {
-            self.len -= 1;
-            Some(unsafe { ptr::read(self.data[self.len].as_ptr()) })
+            self.set_len(self.len() - 1);
+            Some(unsafe { ptr::read(self.data[self.len()].as_ptr()) })
         }
     }
 
-    /// Returns a reference to the element at the given index,
+    /// Returns a reference to the element or subslice at the given index,
     /// returning `None` if out of bounds
-    pub fn get(&self, index: usize) -> Option<&T> {
-        if index < self.len {
-            Some(unsafe { &*self.data[index].as_ptr() })
-        } else {
-            None
-        }
+    pub fn get<I>(&self, index: I) -> Option<&I::Output>
+    where
+        I: SliceIndex<[T]>,
+    {
+        self.as_slice().get(index)
     }
 
-    /// Returns a mutable reference to the element at the given index,
+    /// Returns a mutable reference to the element or subslice at the given index,
     /// returning `None` if out of bounds
-    pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
-        if index < self.len {
-            Some(unsafe { &mut *self.data[index].as_mut_ptr() })
-        } else {
-            None
-        }
+    pub fn get_mut<I>(&mut self, index: I) -> Option<&mut I::Output>
+    where
+        I: SliceIndex<[T]>,
+    {
+        self.as_mut_slice().get_mut(index)
     }
 
     /// Returns a reference to the first element, returning `None` if empty
@@ -92,8 +130,8 @@ impl<T, const N: usize> FixedVec<T, N> {
 
     /// Returns a reference to the last element, returning `None` if empty
     pub fn last(&self) -> Option<&T> {
-        if self.len > 0 {
-            self.get(self.len - 1)
+        if !self.is_empty() {
+            self.get(self.len() - 1)
         } else {
             None
         }
@@ -106,8 +144,8 @@ impl<T, const N: usize> FixedVec<T, N> {
 
     /// Returns a mutable reference to the last element, returning `None` if empty
     pub fn last_mut(&mut self) -> Option<&mut T> {
-        if self.len > 0 {
-            let last_index = self.len - 1;
+        if !self.is_empty() {
+            let last_index = self.len() - 1;
             self.get_mut(last_index)
         } else {
             None
@@ -117,25 +155,22 @@ impl<T, const N: usize> FixedVec<T, N> {
     /// Inserts an element at the given index, shifting all elements after the index to the right
     /// and returning an error if the vector is full or the index is invalid
     pub fn insert(&mut self, index: usize, value: T) -> Result<(), FixedVecError> {
-        if self.len >= N {
+        if self.len() >= N {
             return Err(FixedVecError::Full);
         }
-        if index > self.len {
+        if index > self.len() {
             return Err(FixedVecError::IndexOutOfBounds);
         }
 
-        for i in (index..self.len).rev() {
-            unsafe {
-                let src = self.data[i].as_ptr();
-                let dst = self.data[i + 1].as_mut_ptr();
-                ptr::copy_nonoverlapping(src, dst, 1);
-            }
+        unsafe {
+            let at = self.data_ptr().add(index);
+            ptr::copy(at, at.add(1), self.len() - index);
         }
 
         unsafe {
             ptr::write(self.data[index].as_mut_ptr(), value);
         }
-        self.len += 1;
+        self.set_len(self.len() + 1);
         Ok(())
     }
 
@@ -143,29 +178,25 @@ impl<T, const N: usize> FixedVec<T, N> {
     /// shifting all elements after the index to the left and
     /// returning `None` if the index is out of bounds
     pub fn remove(&mut self, index: usize) -> Option<T> {
-        if index >= self.len {
+        if index >= self.len() {
             return None;
         }
 
         let value = unsafe { ptr::read(self.data[index].as_ptr()) };
 
-        // Shift elements to the left
-        for i in index..self.len - 1 {
-            unsafe {
-                let src = self.data[i + 1].as_ptr();
-                let dst = self.data[i].as_mut_ptr();
-                ptr::copy_nonoverlapping(src, dst, 1);
-            }
+        unsafe {
+            let at = self.data_ptr().add(index);
+            ptr::copy(at.add(1), at, self.len() - index - 1);
         }
 
-        self.len -= 1;
+        self.set_len(self.len() - 1);
         Some(value)
     }
 
     /// Swaps two elements in the vector, panicking if either index is out of bounds
     pub fn swap(&mut self, a: usize, b: usize) {
-        assert!(a < self.len, "Index {} out of bounds", a);
-        assert!(b < self.len, "Index {} out of bounds", b);
+        assert!(a < self.len(), "Index {} out of bounds", a);
+        assert!(b < self.len(), "Index {} out of bounds", b);
 
         if a != b {
             unsafe {
@@ -179,7 +210,7 @@ impl<T, const N: usize> FixedVec<T, N> {
     /// Reverses the order of elements
     pub fn reverse(&mut self) {
         let mut left = 0;
-        let mut right = self.len;
+        let mut right = self.len();
 
         while left < right {
             right -= 1;
@@ -197,48 +228,280 @@ impl<T, const N: usize> FixedVec<T, N> {
     /// If current length is greater than `len`, the vec is truncated
     /// to exactly `len` elements
     pub fn truncate(&mut self, len: usize) {
-        while self.len > len {
+        while self.len() > len {
             self.pop();
         }
     }
 
+    /// Removes and returns the element at the given index, replacing it with the last
+    /// element instead of shifting everything after it, and returning `None` if the
+    /// index is out of bounds
+    pub fn swap_remove(&mut self, index: usize) -> Option<T> {
+        if index >= self.len() {
+            return None;
+        }
+
+        self.set_len(self.len() - 1);
+        unsafe {
+            let base = self.data_ptr();
+            let value = ptr::read(base.add(index));
+            ptr::copy(base.add(self.len()), base.add(index), 1);
+            Some(value)
+        }
+    }
+
+    /// Splits the vector in two at the given index, returning everything from `at`
+    /// onwards, panicking if `at` is out of bounds
+    pub fn split_off(&mut self, at: usize) -> Self {
+        assert!(at <= self.len(), "Index {} out of bounds", at);
+
+        let mut other = Self::new();
+        let count = self.len() - at;
+        unsafe {
+            ptr::copy_nonoverlapping(self.data_ptr().add(at), other.data_ptr(), count);
+        }
+        self.set_len(at);
+        other.set_len(count);
+        other
+    }
+
+    /// Resizes the vector in place, filling new slots with the results of `f`
+    /// and returning an error (with the vector unchanged) if `new_len` exceeds capacity
+    pub fn resize_with<F>(&mut self, new_len: usize, mut f: F) -> Result<(), FixedVecError>
+    where
+        F: FnMut() -> T,
+    {
+        if new_len > N {
+            return Err(FixedVecError::Full);
+        }
+
+        self.truncate(new_len);
+        while self.len() < new_len {
+            unsafe { self.push_unchecked(f()) };
+        }
+        Ok(())
+    }
+
+    /// Keeps only the elements for which `f` returns `true`, in order
+    pub fn retain<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&T) -> bool,
+    {
+        self.retain_mut(|item| f(item));
+    }
+
+    /// Keeps only the elements for which `f` returns `true`, in order, letting `f`
+    /// modify them along the way
+    pub fn retain_mut<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&mut T) -> bool,
+    {
+        let mut compact = Compact::new(self, 0);
+        let base = compact.vec.data_ptr();
+
+        while compact.read < compact.len {
+            let current = unsafe { &mut *base.add(compact.read) };
+            if f(current) {
+                unsafe { ptr::copy(base.add(compact.read), base.add(compact.write), 1) };
+                compact.write += 1;
+                compact.read += 1;
+            } else {
+                // step past it first, so a panicking destructor can't drop it twice
+                compact.read += 1;
+                unsafe { ptr::drop_in_place(current) };
+            }
+        }
+    }
+
+    /// Removes consecutive elements that map to the same key, keeping the first
+    pub fn dedup_by_key<F, K>(&mut self, mut key: F)
+    where
+        F: FnMut(&mut T) -> K,
+        K: PartialEq,
+    {
+        self.dedup_by(|a, b| key(a) == key(b));
+    }
+
+    /// Removes consecutive elements for which `same_bucket(current, previous)` returns
+    /// `true`, keeping the first of each run
+    pub fn dedup_by<F>(&mut self, mut same_bucket: F)
+    where
+        F: FnMut(&mut T, &mut T) -> bool,
+    {
+        if self.len() <= 1 {
+            return;
+        }
+
+        let mut compact = Compact::new(self, 1);
+        let base = compact.vec.data_ptr();
+
+        while compact.read < compact.len {
+            let (current, previous) = unsafe {
+                (
+                    &mut *base.add(compact.read),
+                    &mut *base.add(compact.write - 1),
+                )
+            };
+
+            if same_bucket(current, previous) {
+                compact.read += 1;
+                unsafe { ptr::drop_in_place(current) };
+            } else {
+                unsafe { ptr::copy(base.add(compact.read), base.add(compact.write), 1) };
+                compact.write += 1;
+                compact.read += 1;
+            }
+        }
+    }
+
+    /// Removes the elements in `range` and returns them as an iterator.
+    ///
+    /// Elements the iterator doesn't yield are dropped along with it. If the iterator
+    /// is leaked instead, the vector is left truncated to the start of the range.
+    /// Panics if the range is out of bounds.
+    pub fn drain<R>(&mut self, range: R) -> FixedVecDrain<'_, T, N, L>
+    where
+        R: RangeBounds<usize>,
+    {
+        let (start, end) = bounds(range, self.len());
+        let tail_len = self.len() - end;
+        self.set_len(start);
+
+        FixedVecDrain {
+            vec: self,
+            front: start,
+            back: end,
+            tail_start: end,
+            tail_len,
+        }
+    }
+
+    /// Replaces the elements in `range` with the items of `replace_with`, returning the
+    /// removed elements.
+    ///
+    /// Returns an error with the vector unchanged if the result wouldn't fit, in which
+    /// case the items taken from `replace_with` are dropped. Panics if the range is out
+    /// of bounds.
+    pub fn splice<R, I>(&mut self, range: R, replace_with: I) -> Result<Self, FixedVecError>
+    where
+        R: RangeBounds<usize>,
+        I: IntoIterator<Item = T>,
+    {
+        let (start, end) = bounds(range, self.len());
+        let room = N - (self.len() - (end - start));
+
+        let mut replacement = Self::new();
+        for item in replace_with {
+            if replacement.len() == room {
+                return Err(FixedVecError::Full);
+            }
+            unsafe { replacement.push_unchecked(item) };
+        }
+
+        let mut removed = Self::new();
+        for item in self.drain(start..end) {
+            unsafe { removed.push_unchecked(item) };
+        }
+
+        let tail_len = self.len() - start;
+        unsafe {
+            let base = self.data_ptr();
+            let count = replacement.len();
+            ptr::copy(base.add(start), base.add(start + count), tail_len);
+            ptr::copy_nonoverlapping(replacement.data_ptr(), base.add(start), count);
+            replacement.set_len(0);
+            self.set_len(self.len() + count);
+        }
+        Ok(removed)
+    }
+
+    #[inline]
+    fn data_ptr(&mut self) -> *mut T {
+        self.data.as_mut_ptr() as *mut T
+    }
+
     pub fn iter(&self) -> FixedVecIter<'_, T> {
         FixedVecIter {
-            data: self.as_slice(),
-            index: 0,
+            inner: self.as_slice().iter(),
         }
     }
 
     pub fn iter_mut(&mut self) -> FixedVecIterMut<'_, T> {
         FixedVecIterMut {
-            data: self.as_mut_slice(),
-            index: 0,
+            inner: self.as_mut_slice().iter_mut(),
         }
     }
 
     pub fn as_slice(&self) -> &[T] {
-        unsafe { slice::from_raw_parts(self.data.as_ptr() as *const T, self.len) }
+        unsafe { slice::from_raw_parts(self.data.as_ptr() as *const T, self.len()) }
     }
 
     pub fn as_mut_slice(&mut self) -> &mut [T] {
-        unsafe { slice::from_raw_parts_mut(self.data.as_mut_ptr() as *mut T, self.len) }
+        unsafe { slice::from_raw_parts_mut(self.data.as_mut_ptr() as *mut T, self.len()) }
+    }
+
+    /// # Safety
+    ///
+    /// The vector must not be full
+    #[inline]
+    const unsafe fn push_unchecked(&mut self, value: T) {
+        unsafe {
+            ptr::write(self.data[self.len()].as_mut_ptr(), value);
+        }
+        self.set_len(self.len() + 1);
     }
 
     /// Attempts to push an element, returning the element if full
-    pub fn try_push(&mut self, value: T) -> Result<(), T> {
-        if self.len >= N {
+    pub const fn try_push(&mut self, value: T) -> Result<(), T> {
+        if self.len() >= N {
             return Err(value);
         }
 
         unsafe {
-            ptr::write(self.data[self.len].as_mut_ptr(), value);
+            ptr::write(self.data[self.len()].as_mut_ptr(), value);
+        }
+        self.set_len(self.len() + 1);
+        Ok(())
+    }
+
+    /// Clones and appends every element of `other`, or returns an error with the
+    /// vector unchanged if they don't all fit
+    pub fn extend_from_slice(&mut self, other: &[T]) -> Result<(), FixedVecError>
+    where
+        T: Clone,
+    {
+        if other.len() > self.remaining_capacity() {
+            return Err(FixedVecError::Full);
+        }
+
+        for item in other {
+            unsafe { self.push_unchecked(item.clone()) };
         }
-        self.len += 1;
         Ok(())
     }
 
+    /// Resizes the vector in place, filling new slots with clones of `value`
+    /// and returning an error (with the vector unchanged) if `new_len` exceeds capacity
+    pub fn resize(&mut self, new_len: usize, value: T) -> Result<(), FixedVecError>
+    where
+        T: Clone,
+    {
+        self.resize_with(new_len, || value.clone())
+    }
+
+    /// Removes consecutive repeated elements, keeping the first of each run
+    pub fn dedup(&mut self)
+    where
+        T: PartialEq,
+    {
+        self.dedup_by(|a, b| a == b);
+    }
+
     /// Extends the vector with the contents of an iterator, returning
     /// the number of elements that didn't fit.
+    ///
+    /// The elements that didn't fit are dropped, use [`try_extend`](Self::try_extend)
+    /// to get them back.
     pub fn extend_from_iter<I>(&mut self, iter: I) -> usize
     where
         I: IntoIterator<Item = T>,
@@ -251,93 +514,772 @@ impl<T, const N: usize> FixedVec<T, N> {
         }
         failed_count
     }
+
+    /// Pushes the items of an iterator until the vector is full.
+    ///
+    /// If an item doesn't fit, returns it along with the rest of the iterator, which
+    /// hasn't been advanced past it, so the caller can flush the vector and resume.
+    pub fn try_extend<I>(&mut self, iter: I) -> Result<(), Overflow<I::IntoIter>>
+    where
+        I: IntoIterator<Item = T>,
+    {
+        let mut iter = iter.into_iter();
+        while let Some(item) = iter.next() {
+            if let Err(item) = self.try_push(item) {
+                return Err(Overflow { item, rest: iter });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Panics if the vector runs out of room, after pushing every item that fit.
+/// Use [`FixedVec::try_extend`] to get the overflow back instead, or
+/// [`FixedVec::extend_from_iter`] to drop it.
+impl<T, const N: usize, L: Length> Extend<T> for FixedVec<T, N, L> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        if self.try_extend(iter).is_err() {
+            panic!("FixedVec capacity exceeded");
+        }
+    }
 }
 
-impl<T, const N: usize> Default for FixedVec<T, N> {
+impl<T, const N: usize, L: Length> Default for FixedVec<T, N, L> {
     fn default() -> Self {
         Self::new()
     }
 }
 
-impl<T, const N: usize> Drop for FixedVec<T, N> {
+impl<T, const N: usize, L: Length> Drop for FixedVec<T, N, L> {
     fn drop(&mut self) {
         self.clear();
     }
 }
 
-impl<T, const N: usize> Index<usize> for FixedVec<T, N> {
-    type Output = T;
+impl<T, const N: usize, L: Length> Deref for FixedVec<T, N, L> {
+    type Target = [T];
+
+    fn deref(&self) -> &Self::Target {
+        self.as_slice()
+    }
+}
+
+impl<T, const N: usize, L: Length> DerefMut for FixedVec<T, N, L> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.as_mut_slice()
+    }
+}
+
+impl<T, const N: usize, L: Length> AsRef<[T]> for FixedVec<T, N, L> {
+    fn as_ref(&self) -> &[T] {
+        self.as_slice()
+    }
+}
+
+impl<T, const N: usize, L: Length> AsMut<[T]> for FixedVec<T, N, L> {
+    fn as_mut(&mut self) -> &mut [T] {
+        self.as_mut_slice()
+    }
+}
+
+impl<T, const N: usize, L: Length> Borrow<[T]> for FixedVec<T, N, L> {
+    fn borrow(&self) -> &[T] {
+        self.as_slice()
+    }
+}
+
+impl<T, const N: usize, L: Length> BorrowMut<[T]> for FixedVec<T, N, L> {
+    fn borrow_mut(&mut self) -> &mut [T] {
+        self.as_mut_slice()
+    }
+}
+
+impl<T, I, const N: usize, L: Length> Index<I> for FixedVec<T, N, L>
+where
+    I: SliceIndex<[T]>,
+{
+    type Output = I::Output;
+
+    fn index(&self, index: I) -> &Self::Output {
+        &self.as_slice()[index]
+    }
+}
+
+impl<T, I, const N: usize, L: Length> IndexMut<I> for FixedVec<T, N, L>
+where
+    I: SliceIndex<[T]>,
+{
+    fn index_mut(&mut self, index: I) -> &mut Self::Output {
+        &mut self.as_mut_slice()[index]
+    }
+}
+
+impl<'a, T, const N: usize, L: Length> IntoIterator for &'a FixedVec<T, N, L> {
+    type Item = &'a T;
+    type IntoIter = FixedVecIter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, T, const N: usize, L: Length> IntoIterator for &'a mut FixedVec<T, N, L> {
+    type Item = &'a mut T;
+    type IntoIter = FixedVecIterMut<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+impl<T: Clone, const N: usize, L: Length> Clone for FixedVec<T, N, L> {
+    fn clone(&self) -> Self {
+        let mut vec = Self::new();
+        for item in self.iter() {
+            unsafe { vec.push_unchecked(item.clone()) };
+        }
+        vec
+    }
+}
+
+impl<T: fmt::Debug, const N: usize, L: Length> fmt::Debug for FixedVec<T, N, L> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T, U, const N: usize, const M: usize, L: Length, K: Length> PartialEq<FixedVec<U, M, K>>
+    for FixedVec<T, N, L>
+where
+    T: PartialEq<U>,
+{
+    fn eq(&self, other: &FixedVec<U, M, K>) -> bool {
+        self.as_slice() == other.as_slice()
+    }
+}
+
+impl<T, U, const N: usize, L: Length> PartialEq<[U]> for FixedVec<T, N, L>
+where
+    T: PartialEq<U>,
+{
+    fn eq(&self, other: &[U]) -> bool {
+        self.as_slice() == other
+    }
+}
+
+impl<T, U, const N: usize, L: Length> PartialEq<&[U]> for FixedVec<T, N, L>
+where
+    T: PartialEq<U>,
+{
+    fn eq(&self, other: &&[U]) -> bool {
+        self.as_slice() == *other
+    }
+}
+
+impl<T, U, const N: usize, const M: usize, L: Length> PartialEq<[U; M]> for FixedVec<T, N, L>
+where
+    T: PartialEq<U>,
+{
+    fn eq(&self, other: &[U; M]) -> bool {
+        self.as_slice() == other
+    }
+}
+
+impl<T: Eq, const N: usize, L: Length> Eq for FixedVec<T, N, L> {}
 
-    fn index(&self, index: usize) -> &Self::Output {
-        self.get(index).expect("Index out of bounds")
+impl<T, const N: usize, const M: usize, L: Length, K: Length> PartialOrd<FixedVec<T, M, K>>
+    for FixedVec<T, N, L>
+where
+    T: PartialOrd,
+{
+    fn partial_cmp(&self, other: &FixedVec<T, M, K>) -> Option<Ordering> {
+        self.as_slice().partial_cmp(other.as_slice())
     }
 }
 
-impl<T, const N: usize> IndexMut<usize> for FixedVec<T, N> {
-    fn index_mut(&mut self, index: usize) -> &mut Self::Output {
-        self.get_mut(index).expect("Index out of bounds")
+impl<T: Ord, const N: usize, L: Length> Ord for FixedVec<T, N, L> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.as_slice().cmp(other.as_slice())
+    }
+}
+
+/// Hashes the same as the slice it derefs to, as [`Borrow<[T]>`] requires
+impl<T: Hash, const N: usize, L: Length> Hash for FixedVec<T, N, L> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_slice().hash(state);
+    }
+}
+
+/// Fails to compile if the array is longer than the vector's capacity
+impl<T, const N: usize, const M: usize, L: Length> From<[T; M]> for FixedVec<T, N, L> {
+    fn from(array: [T; M]) -> Self {
+        Self::from_array(array)
+    }
+}
+
+/// Compile-time check that an array of `M` elements fits in a capacity of `N`
+pub(crate) struct Fits<const M: usize, const N: usize>;
+
+impl<const M: usize, const N: usize> Fits<M, N> {
+    pub(crate) const CHECK: () = assert!(M <= N, "array doesn't fit in the capacity");
+}
+
+/// Integer type a [`FixedVec`], [`Stack`](crate::Stack) or
+/// [`FixedString`](crate::FixedString) stores its length in, and a
+/// [`Queue`](crate::Queue) or [`RingBuffer`](crate::RingBuffer) its indices in: `u8`,
+/// `u16`, `u32` or the default `usize`.
+///
+/// Small containers shrink with a small length type, `FixedVec<u8, 8, u8>` takes 9
+/// bytes where `FixedVec<u8, 8>` takes 16. The capacity has to fit in the type:
+///
+/// ```compile_fail
+/// use sakurai::FixedVec;
+///
+/// let vec = FixedVec::<u8, 256, u8>::new();
+/// ```
+pub trait Length: Copy + sealed::Sealed {
+    /// Largest length the type can hold
+    const MAX: usize;
+
+    /// Atomic of the same width, for the indices of [`Queue`](crate::Queue) and
+    /// [`RingBuffer`](crate::RingBuffer)
+    #[doc(hidden)]
+    type Atomic: sealed::AtomicLength;
+}
+
+pub(crate) mod sealed {
+    use core::sync::atomic::Ordering;
+
+    pub trait Sealed {}
+
+    /// All zeroes is a valid atomic holding 0, so containers build one with
+    /// [`mem::zeroed`](core::mem::zeroed) in a `const fn`.
+    pub trait AtomicLength {
+        fn load(&self, order: Ordering) -> usize;
+
+        /// `value` must fit in the integer type, see [`LengthFits`](super::LengthFits)
+        fn store(&self, value: usize, order: Ordering);
+    }
+}
+
+macro_rules! impl_length {
+    ($($int:ty => $atomic:ty),*) => {
+        $(
+            impl sealed::Sealed for $int {}
+
+            impl Length for $int {
+                const MAX: usize = if <$int>::BITS < usize::BITS {
+                    <$int>::MAX as usize
+                } else {
+                    usize::MAX
+                };
+
+                type Atomic = $atomic;
+            }
+
+            impl sealed::AtomicLength for $atomic {
+                #[inline]
+                fn load(&self, order: atomic::Ordering) -> usize {
+                    <$atomic>::load(self, order) as usize
+                }
+
+                #[inline]
+                fn store(&self, value: usize, order: atomic::Ordering) {
+                    <$atomic>::store(self, value as $int, order)
+                }
+            }
+        )*
+    };
+}
+
+impl_length!(u8 => AtomicU8, u16 => AtomicU16, u32 => AtomicU32, usize => AtomicUsize);
+
+/// Compile-time check that a capacity of `N` fits in the length type `L`
+pub(crate) struct LengthFits<L, const N: usize>(PhantomData<L>);
+
+impl<L: Length, const N: usize> LengthFits<L, N> {
+    pub(crate) const CHECK: () = assert!(N <= L::MAX, "capacity doesn't fit in the length type");
+}
+
+// `Length` is sealed, so `L` is one of the unsigned integers above and its size says
+// which. That keeps the conversions usable in `const fn` without const trait bounds,
+// which callers would need a nightly feature for.
+
+pub(crate) const fn length_to_usize<L: Length>(len: &L) -> usize {
+    unsafe {
+        match size_of::<L>() {
+            1 => mem::transmute_copy::<L, u8>(len) as usize,
+            2 => mem::transmute_copy::<L, u16>(len) as usize,
+            4 => mem::transmute_copy::<L, u32>(len) as usize,
+            _ => mem::transmute_copy::<L, usize>(len),
+        }
+    }
+}
+
+/// `len` must fit in `L`, which [`LengthFits`] ensures for lengths up to the capacity
+pub(crate) const fn length_from_usize<L: Length>(len: usize) -> L {
+    unsafe {
+        match size_of::<L>() {
+            1 => mem::transmute_copy(&(len as u8)),
+            2 => mem::transmute_copy(&(len as u16)),
+            4 => mem::transmute_copy(&(len as u32)),
+            _ => mem::transmute_copy(&len),
+        }
+    }
+}
+
+/// Builds a [`FixedVec`](crate::FixedVec) from a list of elements, or from one element
+/// repeated, like `vec!`. The capacity comes from the type being built.
+///
+/// Works in `const` and `static` items:
+///
+/// ```
+/// use sakurai::{FixedVec, fixedvec};
+///
+/// static PORTS: FixedVec<u16, 8> = fixedvec![22, 80, 443];
+/// const ZEROES: FixedVec<u8, 4> = fixedvec![0; 4];
+///
+/// assert_eq!(PORTS, [22, 80, 443]);
+/// assert_eq!(ZEROES.len(), 4);
+/// ```
+///
+/// More elements than the capacity is a compile error:
+///
+/// ```compile_fail
+/// use sakurai::{FixedVec, fixedvec};
+///
+/// let vec: FixedVec<u8, 2> = fixedvec![1, 2, 3];
+/// ```
+#[macro_export]
+macro_rules! fixedvec {
+    ($value:expr; $n:expr) => {
+        $crate::fixedvec::FixedVec::from_array([$value; $n])
+    };
+    ($($value:expr),* $(,)?) => {
+        $crate::fixedvec::FixedVec::from_array([$($value),*])
+    };
+}
+
+impl<T: Clone, const N: usize, L: Length> TryFrom<&[T]> for FixedVec<T, N, L> {
+    type Error = FixedVecError;
+
+    fn try_from(slice: &[T]) -> Result<Self, Self::Error> {
+        if slice.len() > N {
+            return Err(FixedVecError::Full);
+        }
+
+        let mut vec = Self::new();
+        for item in slice {
+            unsafe { vec.push_unchecked(item.clone()) };
+        }
+        Ok(vec)
+    }
+}
+
+/// Hands the `Vec` back if it's longer than the vector's capacity
+#[cfg(feature = "alloc")]
+impl<T, const N: usize, L: Length> TryFrom<Vec<T>> for FixedVec<T, N, L> {
+    type Error = Vec<T>;
+
+    fn try_from(source: Vec<T>) -> Result<Self, Self::Error> {
+        if source.len() > N {
+            return Err(source);
+        }
+
+        let mut vec = Self::new();
+        for item in source {
+            unsafe { vec.push_unchecked(item) };
+        }
+        Ok(vec)
+    }
+}
+
+/// Appends UTF-8 text, failing with [`fmt::Error`] on the first piece that doesn't fit.
+/// Pieces of the same `write!` that came before it stay written.
+impl<const N: usize, L: Length> fmt::Write for FixedVec<u8, N, L> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.extend_from_slice(s.as_bytes()).map_err(|_| fmt::Error)
+    }
+}
+
+/// Appends as much as fits, so `write_all` fails with
+/// [`WriteZero`](std::io::ErrorKind::WriteZero) once the vector is full
+#[cfg(feature = "std")]
+impl<const N: usize, L: Length> std::io::Write for FixedVec<u8, N, L> {
+    fn write(&mut self, bytes: &[u8]) -> std::io::Result<usize> {
+        let count = bytes.len().min(self.remaining_capacity());
+        let appended = self.extend_from_slice(&bytes[..count]);
+        debug_assert!(appended.is_ok());
+        Ok(count)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(feature = "embedded-io")]
+impl<const N: usize, L: Length> embedded_io::ErrorType for FixedVec<u8, N, L> {
+    type Error = FixedVecError;
+}
+
+#[cfg(feature = "embedded-io")]
+impl<const N: usize, L: Length> embedded_io::Write for FixedVec<u8, N, L> {
+    fn write(&mut self, bytes: &[u8]) -> Result<usize, Self::Error> {
+        if !bytes.is_empty() && self.is_full() {
+            return Err(FixedVecError::Full);
+        }
+
+        let count = bytes.len().min(self.remaining_capacity());
+        self.extend_from_slice(&bytes[..count])?;
+        Ok(count)
+    }
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+impl<T, const N: usize, L: Length> IntoIterator for FixedVec<T, N, L> {
+    type Item = T;
+    type IntoIter = FixedVecIntoIter<T, N>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        let vec = ManuallyDrop::new(self);
+        FixedVecIntoIter {
+            data: unsafe { ptr::read(&vec.data) },
+            start: 0,
+            end: vec.len(),
+        }
     }
 }
 
 pub struct FixedVecIter<'a, T> {
-    data: &'a [T],
-    index: usize,
+    inner: slice::Iter<'a, T>,
+}
+
+impl<'a, T> FixedVecIter<'a, T> {
+    /// Returns the remaining elements as a slice
+    pub fn as_slice(&self) -> &'a [T] {
+        self.inner.as_slice()
+    }
+}
+
+impl<'a, T> Clone for FixedVecIter<'a, T> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
 }
 
 impl<'a, T> Iterator for FixedVecIter<'a, T> {
     type Item = &'a T;
 
+    #[inline]
     fn next(&mut self) -> Option<Self::Item> {
-        if self.index < self.data.len() {
-            let item = &self.data[self.index];
-            self.index += 1;
-            Some(item)
-        } else {
-            None
-        }
+        self.inner.next()
     }
 
+    #[inline]
     fn size_hint(&self) -> (usize, Option<usize>) {
-        let remaining = self.data.len() - self.index;
-        (remaining, Some(remaining))
+        self.inner.size_hint()
+    }
+
+    #[inline]
+    fn nth(&mut self, n: usize) -> Option<Self::Item> {
+        self.inner.nth(n)
+    }
+}
+
+impl<'a, T> DoubleEndedIterator for FixedVecIter<'a, T> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back()
+    }
+
+    #[inline]
+    fn nth_back(&mut self, n: usize) -> Option<Self::Item> {
+        self.inner.nth_back(n)
     }
 }
 
 impl<'a, T> ExactSizeIterator for FixedVecIter<'a, T> {
     fn len(&self) -> usize {
-        self.data.len() - self.index
+        self.inner.len()
     }
 }
 
+impl<'a, T> FusedIterator for FixedVecIter<'a, T> {}
+
 /// Mutable iterator over fixed vector elements
 pub struct FixedVecIterMut<'a, T> {
-    data: &'a mut [T],
-    index: usize,
+    inner: slice::IterMut<'a, T>,
+}
+
+impl<'a, T> FixedVecIterMut<'a, T> {
+    /// Returns the remaining elements as a mutable slice
+    pub fn into_slice(self) -> &'a mut [T] {
+        self.inner.into_slice()
+    }
 }
 
 impl<'a, T> Iterator for FixedVecIterMut<'a, T> {
     type Item = &'a mut T;
 
+    #[inline]
     fn next(&mut self) -> Option<Self::Item> {
-        if self.index < self.data.len() {
-            let ptr = self.data.as_mut_ptr();
-            let item = unsafe { &mut *ptr.add(self.index) };
-            self.index += 1;
-            Some(item)
-        } else {
-            None
+        self.inner.next()
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+
+    #[inline]
+    fn nth(&mut self, n: usize) -> Option<Self::Item> {
+        self.inner.nth(n)
+    }
+}
+
+impl<'a, T> DoubleEndedIterator for FixedVecIterMut<'a, T> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back()
+    }
+
+    #[inline]
+    fn nth_back(&mut self, n: usize) -> Option<Self::Item> {
+        self.inner.nth_back(n)
+    }
+}
+
+impl<'a, T> ExactSizeIterator for FixedVecIterMut<'a, T> {
+    fn len(&self) -> usize {
+        self.inner.len()
+    }
+}
+
+impl<'a, T> FusedIterator for FixedVecIterMut<'a, T> {}
+
+/// Iterator returned by [`FixedVec::drain`]
+pub struct FixedVecDrain<'a, T, const N: usize, L: Length = usize> {
+    vec: &'a mut FixedVec<T, N, L>,
+    front: usize,
+    back: usize,
+    tail_start: usize,
+    tail_len: usize,
+}
+
+impl<'a, T, const N: usize, L: Length> FixedVecDrain<'a, T, N, L> {
+    /// Returns the elements not yet yielded as a slice
+    pub fn as_slice(&self) -> &[T] {
+        let remaining = &self.vec.data[self.front..self.back];
+        unsafe { slice::from_raw_parts(remaining.as_ptr() as *const T, remaining.len()) }
+    }
+}
+
+impl<'a, T, const N: usize, L: Length> Iterator for FixedVecDrain<'a, T, N, L> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.front == self.back {
+            return None;
         }
+
+        let item = unsafe { ptr::read(self.vec.data_ptr().add(self.front)) };
+        self.front += 1;
+        Some(item)
     }
 
     fn size_hint(&self) -> (usize, Option<usize>) {
-        let remaining = self.data.len() - self.index;
+        let remaining = self.back - self.front;
         (remaining, Some(remaining))
     }
 }
 
-impl<'a, T> ExactSizeIterator for FixedVecIterMut<'a, T> {
+impl<'a, T, const N: usize, L: Length> DoubleEndedIterator for FixedVecDrain<'a, T, N, L> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.front == self.back {
+            return None;
+        }
+
+        self.back -= 1;
+        Some(unsafe { ptr::read(self.vec.data_ptr().add(self.back)) })
+    }
+}
+
+impl<'a, T, const N: usize, L: Length> ExactSizeIterator for FixedVecDrain<'a, T, N, L> {
+    fn len(&self) -> usize {
+        self.back - self.front
+    }
+}
+
+impl<'a, T, const N: usize, L: Length> FusedIterator for FixedVecDrain<'a, T, N, L> {}
+
+impl<'a, T, const N: usize, L: Length> Drop for FixedVecDrain<'a, T, N, L> {
+    fn drop(&mut self) {
+        let base = self.vec.data_ptr();
+        let unyielded =
+            ptr::slice_from_raw_parts_mut(unsafe { base.add(self.front) }, self.back - self.front);
+        self.front = self.back;
+
+        // if a destructor panics, the tail is leaked rather than dropped twice
+        unsafe {
+            ptr::drop_in_place(unyielded);
+            ptr::copy(
+                base.add(self.tail_start),
+                base.add(self.vec.len()),
+                self.tail_len,
+            );
+        }
+        self.vec.set_len(self.vec.len() + self.tail_len);
+    }
+}
+
+/// Guard for the in-place filters. Elements before `write` are kept and elements from
+/// `read` on are unprocessed. Dropping it, even while unwinding, closes the gap.
+struct Compact<'a, T, const N: usize, L: Length> {
+    vec: &'a mut FixedVec<T, N, L>,
+    read: usize,
+    write: usize,
+    len: usize,
+}
+
+impl<'a, T, const N: usize, L: Length> Compact<'a, T, N, L> {
+    fn new(vec: &'a mut FixedVec<T, N, L>, start: usize) -> Self {
+        let len = vec.len();
+        vec.set_len(0);
+        Self {
+            vec,
+            read: start,
+            write: start,
+            len,
+        }
+    }
+}
+
+impl<'a, T, const N: usize, L: Length> Drop for Compact<'a, T, N, L> {
+    fn drop(&mut self) {
+        let unprocessed = self.len - self.read;
+        unsafe {
+            let base = self.vec.data_ptr();
+            ptr::copy(base.add(self.read), base.add(self.write), unprocessed);
+        }
+        self.vec.set_len(self.write + unprocessed);
+    }
+}
+
+/// Resolves a range against a length, panicking if it's out of bounds
+pub(crate) fn bounds<R: RangeBounds<usize>>(range: R, len: usize) -> (usize, usize) {
+    let start = match range.start_bound() {
+        Bound::Included(&start) => start,
+        Bound::Excluded(&start) => start.checked_add(1).expect("range bound overflow"),
+        Bound::Unbounded => 0,
+    };
+    let end = match range.end_bound() {
+        Bound::Included(&end) => end.checked_add(1).expect("range bound overflow"),
+        Bound::Excluded(&end) => end,
+        Bound::Unbounded => len,
+    };
+
+    assert!(
+        start <= end && end <= len,
+        "Range {}..{} out of bounds",
+        start,
+        end
+    );
+    (start, end)
+}
+
+/// Owning iterator over fixed vector elements
+pub struct FixedVecIntoIter<T, const N: usize> {
+    data: [MaybeUninit<T>; N],
+    start: usize,
+    end: usize,
+}
+
+impl<T, const N: usize> FixedVecIntoIter<T, N> {
+    /// Returns the remaining elements as a slice
+    pub fn as_slice(&self) -> &[T] {
+        let remaining = &self.data[self.start..self.end];
+        unsafe { slice::from_raw_parts(remaining.as_ptr() as *const T, remaining.len()) }
+    }
+}
+
+impl<T, const N: usize> Iterator for FixedVecIntoIter<T, N> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.start == self.end {
+            return None;
+        }
+
+        let item = unsafe { ptr::read(self.data[self.start].as_ptr()) };
+        self.start += 1;
+        Some(item)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.end - self.start;
+        (remaining, Some(remaining))
+    }
+}
+
+impl<T, const N: usize> DoubleEndedIterator for FixedVecIntoIter<T, N> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.start == self.end {
+            return None;
+        }
+
+        self.end -= 1;
+        Some(unsafe { ptr::read(self.data[self.end].as_ptr()) })
+    }
+}
+
+impl<T, const N: usize> ExactSizeIterator for FixedVecIntoIter<T, N> {
     fn len(&self) -> usize {
-        self.data.len() - self.index
+        self.end - self.start
+    }
+}
+
+impl<T, const N: usize> FusedIterator for FixedVecIntoIter<T, N> {}
+
+impl<T, const N: usize> Drop for FixedVecIntoIter<T, N> {
+    fn drop(&mut self) {
+        for item in &mut self.data[self.start..self.end] {
+            unsafe { item.assume_init_drop() };
+        }
+    }
+}
+
+/// Returned by [`FixedVec::try_extend`] when the vector fills up: the item that
+/// didn't fit, and the iterator it came from
+pub struct Overflow<I: Iterator> {
+    pub item: I::Item,
+    pub rest: I,
+}
+
+/// Yields the item that didn't fit, then the rest of the iterator
+impl<I: Iterator> IntoIterator for Overflow<I> {
+    type Item = I::Item;
+    type IntoIter = Chain<Once<I::Item>, I>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        iter::once(self.item).chain(self.rest)
+    }
+}
+
+impl<I> fmt::Debug for Overflow<I>
+where
+    I: Iterator,
+    I::Item: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Overflow")
+            .field("item", &self.item)
+            .finish_non_exhaustive()
     }
 }
 
@@ -347,9 +1289,21 @@ pub enum FixedVecError {
     IndexOutOfBounds,
 }
 
+#[cfg(feature = "embedded-io")]
+impl embedded_io::Error for FixedVecError {
+    fn kind(&self) -> embedded_io::ErrorKind {
+        match self {
+            FixedVecError::Full => embedded_io::ErrorKind::WriteZero,
+            FixedVecError::IndexOutOfBounds => embedded_io::ErrorKind::InvalidInput,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::rc::Rc;
+    use std::string::{String, ToString};
 
     #[test]
     fn test_push_pop() {
@@ -520,4 +1474,365 @@ mod tests {
         assert_eq!(failed, 5); // 5 will fail
         assert_eq!(vec.as_slice(), &[0, 1, 2, 3, 4]);
     }
+
+    #[test]
+    fn test_try_extend() {
+        let mut vec = FixedVec::<i32, 3>::new();
+        vec.try_extend([1, 2]).unwrap();
+
+        let overflow = vec.try_extend(3..10).unwrap_err();
+        assert_eq!(vec, [1, 2, 3]);
+        assert_eq!(overflow.item, 4);
+        assert_eq!(overflow.rest.clone().next(), Some(5));
+
+        // flush and resume where it stopped
+        let mut flushed = std::vec::Vec::new();
+        let mut result = Err(overflow);
+        while let Err(Overflow { item, rest }) = result {
+            flushed.extend(vec.drain(..));
+            vec.push(item).unwrap();
+            result = vec.try_extend(rest);
+        }
+        flushed.extend(vec.drain(..));
+        assert_eq!(flushed, [1, 2, 3, 4, 5, 6, 7, 8, 9]);
+    }
+
+    #[test]
+    fn test_extend() {
+        let mut vec = FixedVec::<i32, 4>::new();
+        vec.extend([1, 2]);
+        vec.extend(Some(3));
+        assert_eq!(vec, [1, 2, 3]);
+    }
+
+    #[test]
+    #[should_panic(expected = "FixedVec capacity exceeded")]
+    fn test_extend_overflow() {
+        let mut vec = FixedVec::<i32, 2>::new();
+        vec.extend(0..3);
+    }
+
+    #[test]
+    fn test_slice_api() {
+        let mut vec = FixedVec::<i32, 8>::new();
+        vec.extend_from_iter([5, 3, 8, 1, 9, 2]);
+
+        vec.sort_unstable();
+        assert_eq!(&vec[..], &[1, 2, 3, 5, 8, 9]);
+        assert_eq!(vec.binary_search(&5), Ok(3));
+        assert!(vec.contains(&8));
+        assert_eq!(vec.windows(2).count(), 5);
+        assert_eq!(vec.chunks(4).len(), 2);
+
+        assert_eq!(&vec[1..3], &[2, 3]);
+        assert_eq!(&vec[4..], &[8, 9]);
+        assert_eq!(&vec[..=1], &[1, 2]);
+        assert_eq!(vec.get(2..4), Some(&[3, 5][..]));
+        assert_eq!(vec.get(5..7), None);
+
+        vec[..2].copy_from_slice(&[0, 0]);
+        assert_eq!(vec.as_ref(), &[0, 0, 3, 5, 8, 9]);
+    }
+
+    #[test]
+    fn test_double_ended_iter() {
+        let mut vec = FixedVec::<i32, 8>::new();
+        vec.extend_from_iter(0..6);
+
+        let mut iter = vec.iter();
+        assert_eq!(iter.next(), Some(&0));
+        assert_eq!(iter.next_back(), Some(&5));
+        assert_eq!(iter.len(), 4);
+        assert_eq!(iter.as_slice(), &[1, 2, 3, 4]);
+        assert_eq!(
+            iter.rev().copied().collect::<std::vec::Vec<_>>(),
+            [4, 3, 2, 1]
+        );
+
+        for value in vec.iter_mut().rev().step_by(2) {
+            *value = -1;
+        }
+        assert_eq!(vec.as_slice(), &[0, -1, 2, -1, 4, -1]);
+
+        let mut total = 0;
+        for value in &vec {
+            total += value;
+        }
+        assert_eq!(total, 3);
+    }
+
+    #[test]
+    fn test_traits() {
+        let a = FixedVec::<i32, 4>::from([1, 2, 3]);
+        let b = a.clone();
+        let c = FixedVec::<i32, 8>::from([1, 2, 4]);
+
+        assert_eq!(a, b);
+        assert_eq!(a, [1, 2, 3]);
+        assert_eq!(a, &[1, 2, 3][..]);
+        assert_ne!(a, c);
+        assert!(a < c);
+        assert_eq!(b.cmp(&a), Ordering::Equal);
+        assert_eq!(std::format!("{:?}", a), "[1, 2, 3]");
+
+        // usable as a map key, and found through a borrowed slice
+        let mut set = std::collections::HashSet::new();
+        set.insert(a);
+        assert!(set.contains(&[1, 2, 3][..]));
+    }
+
+    #[test]
+    fn test_conversions() {
+        let vec = FixedVec::<String, 3>::try_from(&["a".to_string(), "b".to_string()][..]).unwrap();
+        assert_eq!(vec, ["a", "b"]);
+        assert_eq!(
+            FixedVec::<u8, 2>::try_from(&[1, 2, 3][..]),
+            Err(FixedVecError::Full)
+        );
+
+        let empty = FixedVec::<u8, 2>::from([]);
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn test_into_iter() {
+        let vec = FixedVec::<String, 4>::from(["a".to_string(), "b".to_string(), "c".to_string()]);
+        let mut iter = vec.into_iter();
+        assert_eq!(iter.next_back().as_deref(), Some("c"));
+        assert_eq!(iter.as_slice(), ["a".to_string(), "b".to_string()]);
+        assert_eq!(iter.next().as_deref(), Some("a"));
+
+        let tracker = Rc::new(());
+        let vec = FixedVec::<Rc<()>, 4>::from([tracker.clone(), tracker.clone(), tracker.clone()]);
+        let mut iter = vec.into_iter();
+        iter.next();
+        assert_eq!(Rc::strong_count(&tracker), 3);
+        drop(iter);
+        assert_eq!(Rc::strong_count(&tracker), 1);
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn test_try_from_vec() {
+        let vec = FixedVec::<i32, 4>::try_from(std::vec![1, 2, 3]).unwrap();
+        assert_eq!(vec, [1, 2, 3]);
+        assert_eq!(
+            FixedVec::<i32, 2>::try_from(std::vec![1, 2, 3]),
+            Err(std::vec![1, 2, 3])
+        );
+    }
+
+    #[test]
+    fn test_drain() {
+        let mut vec = FixedVec::<i32, 8>::from([0, 1, 2, 3, 4, 5]);
+        let drained: std::vec::Vec<_> = vec.drain(1..4).collect();
+        assert_eq!(drained, [1, 2, 3]);
+        assert_eq!(vec, [0, 4, 5]);
+
+        // dropping early drops the rest of the range and closes the gap
+        let mut drain = vec.drain(..2);
+        assert_eq!(drain.next_back(), Some(4));
+        drop(drain);
+        assert_eq!(vec, [5]);
+
+        vec.extend_from_slice(&[6, 7]).unwrap();
+        assert_eq!(vec.drain(..).len(), 3);
+        assert!(vec.is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "range bound overflow")]
+    fn test_drain_bound_overflow() {
+        let mut vec = FixedVec::<u32, 4>::from([1, 2, 3]);
+        vec.drain(..=usize::MAX);
+    }
+
+    #[test]
+    fn test_drain_leak() {
+        let tracker = Rc::new(());
+        let mut vec = FixedVec::<Rc<()>, 8>::new();
+        for _ in 0..5 {
+            vec.push(tracker.clone()).unwrap();
+        }
+
+        let mut drain = vec.drain(1..3);
+        drain.next();
+        core::mem::forget(drain);
+
+        // the vector is cut short at the range, and everything after it leaks
+        assert_eq!(vec.len(), 1);
+        drop(vec);
+        assert_eq!(Rc::strong_count(&tracker), 4);
+    }
+
+    #[test]
+    fn test_splice() {
+        let mut vec = FixedVec::<i32, 6>::from([1, 2, 3, 4]);
+
+        let removed = vec.splice(1..3, [7, 8, 9]).unwrap();
+        assert_eq!(removed, [2, 3]);
+        assert_eq!(vec, [1, 7, 8, 9, 4]);
+
+        let removed = vec.splice(..4, [0]).unwrap();
+        assert_eq!(removed, [1, 7, 8, 9]);
+        assert_eq!(vec, [0, 4]);
+
+        assert_eq!(vec.splice(1..1, 10..15), Err(FixedVecError::Full));
+        assert_eq!(vec, [0, 4]);
+        vec.splice(1..1, 10..14).unwrap();
+        assert_eq!(vec, [0, 10, 11, 12, 13, 4]);
+    }
+
+    #[test]
+    fn test_retain() {
+        let mut vec = FixedVec::<i32, 8>::from([1, 2, 3, 4, 5, 6]);
+        vec.retain(|&x| x % 2 == 0);
+        assert_eq!(vec, [2, 4, 6]);
+
+        vec.retain_mut(|x| {
+            *x *= 10;
+            *x > 20
+        });
+        assert_eq!(vec, [40, 60]);
+
+        let tracker = Rc::new(());
+        let mut vec = FixedVec::<(u8, Rc<()>), 8>::new();
+        for i in 0..6 {
+            vec.push((i, tracker.clone())).unwrap();
+        }
+        vec.retain(|(i, _)| *i >= 4);
+        assert_eq!(Rc::strong_count(&tracker), 3);
+    }
+
+    #[test]
+    fn test_retain_panic() {
+        let mut vec = FixedVec::<String, 8>::new();
+        for i in 0..6 {
+            vec.push(i.to_string()).unwrap();
+        }
+
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            vec.retain(|s| {
+                assert_ne!(s, "3");
+                s != "1"
+            })
+        }));
+        assert!(result.is_err());
+        // everything from the panicking element on is kept
+        assert_eq!(vec, ["0", "2", "3", "4", "5"]);
+    }
+
+    #[test]
+    fn test_dedup() {
+        let mut vec = FixedVec::<i32, 8>::from([1, 1, 2, 3, 3, 3, 1, 1]);
+        vec.dedup();
+        assert_eq!(vec, [1, 2, 3, 1]);
+
+        let mut vec = FixedVec::<i32, 8>::from([10, 11, 20, 25, 31]);
+        vec.dedup_by_key(|x| *x / 10);
+        assert_eq!(vec, [10, 20, 31]);
+    }
+
+    #[test]
+    fn test_swap_remove_split_off() {
+        let mut vec = FixedVec::<i32, 8>::from([1, 2, 3, 4, 5]);
+        assert_eq!(vec.swap_remove(1), Some(2));
+        assert_eq!(vec, [1, 5, 3, 4]);
+        assert_eq!(vec.swap_remove(3), Some(4));
+        assert_eq!(vec.swap_remove(3), None);
+
+        let tail = vec.split_off(1);
+        assert_eq!(vec, [1]);
+        assert_eq!(tail, [5, 3]);
+        assert!(vec.split_off(1).is_empty());
+    }
+
+    #[test]
+    fn test_resize_extend() {
+        let mut vec = FixedVec::<i32, 4>::from([1]);
+        vec.resize(3, 0).unwrap();
+        assert_eq!(vec, [1, 0, 0]);
+        assert_eq!(vec.resize(5, 0), Err(FixedVecError::Full));
+        assert_eq!(vec, [1, 0, 0]);
+
+        let mut next = 5;
+        vec.resize_with(4, || {
+            next += 1;
+            next
+        })
+        .unwrap();
+        assert_eq!(vec, [1, 0, 0, 6]);
+        vec.resize(2, 9).unwrap();
+        assert_eq!(vec, [1, 0]);
+
+        assert_eq!(vec.extend_from_slice(&[7, 8, 9]), Err(FixedVecError::Full));
+        assert_eq!(vec, [1, 0]);
+        vec.extend_from_slice(&[7, 8]).unwrap();
+        assert_eq!(vec, [1, 0, 7, 8]);
+    }
+
+    #[test]
+    fn test_const_construction() {
+        static ROUTES: FixedVec<(&str, u16), 4> = fixedvec![("api", 8080), ("web", 80)];
+        const FILLED: FixedVec<u32, 4> = {
+            let mut vec = FixedVec::new();
+            let mut i = 0;
+            while vec.push(i * 10).is_ok() {
+                i += 1;
+            }
+            vec
+        };
+
+        assert_eq!(ROUTES, [("api", 8080), ("web", 80)]);
+        assert_eq!(FILLED, [0, 10, 20, 30]);
+        let sevens: FixedVec<u8, 4> = fixedvec![7; 3];
+        assert_eq!(sevens, [7, 7, 7]);
+
+        let empty: FixedVec<String, 2> = fixedvec![];
+        assert!(empty.is_empty());
+
+        // a rejected push still drops its value at runtime
+        let counter = Rc::new(());
+        let mut full = FixedVec::<_, 1>::from_array([counter.clone()]);
+        assert_eq!(full.push(counter.clone()), Err(FixedVecError::Full));
+        assert_eq!(Rc::strong_count(&counter), 2);
+    }
+
+    #[test]
+    fn test_fmt_write() {
+        use core::fmt::Write;
+
+        let mut packet = FixedVec::<u8, 8>::new();
+        let id = 42;
+        write!(packet, "id={id}").unwrap();
+        assert_eq!(packet, *b"id=42");
+        assert!(write!(packet, "{id}{id}").is_err());
+        assert_eq!(packet, *b"id=4242");
+    }
+
+    #[test]
+    fn test_length_type() {
+        assert_eq!(size_of::<FixedVec<u8, 8, u8>>(), 9);
+        assert_eq!(size_of::<FixedVec<u16, 1000, u16>>(), 2002);
+        assert_eq!(size_of::<FixedVec<u8, 8>>(), 16);
+
+        const SMALL: FixedVec<u8, 4, u8> = fixedvec![1, 2, 3];
+        assert_eq!(SMALL, [1, 2, 3]);
+        let wide: FixedVec<u8, 8> = fixedvec![1, 2, 3];
+        assert_eq!(SMALL, wide);
+
+        let mut vec = FixedVec::<u32, 255, u8>::new();
+        for i in 0..255 {
+            vec.push(i).unwrap();
+        }
+        assert!(vec.is_full());
+        assert_eq!(vec.len(), 255);
+        assert_eq!(vec.push(255), Err(FixedVecError::Full));
+
+        vec.retain(|&i| i % 2 == 0);
+        assert_eq!(vec.len(), 128);
+        assert_eq!(vec.drain(100..).len(), 28);
+        assert_eq!(vec.last(), Some(&198));
+        assert_eq!(vec.into_iter().rev().nth(1), Some(196));
+    }
 }
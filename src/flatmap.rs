@@ -0,0 +1,466 @@
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::iter::FusedIterator;
+use core::mem;
+use core::ops::{Bound, Index, RangeBounds};
+use core::slice;
+
+use crate::fixedvec::{FixedVec, FixedVecIntoIter};
+use crate::sortedvec::{MergeSorted, union_len};
+
+/// Fixed capacity map kept as a [`FixedVec`] of pairs sorted by key.
+///
+/// Has the same methods as [`BTree`](crate::BTree), so either can stand in for the
+/// other, but for small maps a binary search over one contiguous array is faster and
+/// smaller than tree nodes or a hash table.
+pub struct FlatMap<K, V, const N: usize> {
+    entries: FixedVec<(K, V), N>,
+}
+
+impl<K, V, const N: usize> FlatMap<K, V, N>
+where
+    K: Ord,
+{
+    pub const fn new() -> Self {
+        Self {
+            entries: FixedVec::new(),
+        }
+    }
+
+    #[inline]
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    #[inline]
+    pub const fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    #[inline]
+    pub const fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    #[inline]
+    pub const fn is_full(&self) -> bool {
+        self.entries.is_full()
+    }
+
+    /// Inserts a key-value pair into the map, returning the old value if the key already exists
+    pub fn insert(&mut self, key: K, value: V) -> Result<Option<V>, FlatMapError> {
+        match self.search(&key) {
+            Ok(index) => Ok(Some(mem::replace(&mut self.entries[index].1, value))),
+            Err(index) => {
+                self.entries
+                    .insert(index, (key, value))
+                    .map_err(|_| FlatMapError::Full)?;
+                Ok(None)
+            }
+        }
+    }
+
+    /// Get a reference to a value for a given key
+    pub fn get(&self, key: &K) -> Option<&V> {
+        self.get_key_value(key).map(|(_, value)| value)
+    }
+
+    /// Get a mutable reference to a value for a given key
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        let index = self.search(key).ok()?;
+        Some(&mut self.entries[index].1)
+    }
+
+    /// Get references to the stored key and its value for a given key
+    pub fn get_key_value(&self, key: &K) -> Option<(&K, &V)> {
+        let index = self.search(key).ok()?;
+        let (key, value) = &self.entries[index];
+        Some((key, value))
+    }
+
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.search(key).is_ok()
+    }
+
+    /// Removes a key from the map, returning its value if it was present
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        self.remove_entry(key).map(|(_, value)| value)
+    }
+
+    /// Removes a key from the map, returning the stored key and value if it was present
+    pub fn remove_entry(&mut self, key: &K) -> Option<(K, V)> {
+        let index = self.search(key).ok()?;
+        self.entries.remove(index)
+    }
+
+    /// Entry with the smallest key, `None` if empty
+    pub fn first_key_value(&self) -> Option<(&K, &V)> {
+        self.entries.first().map(|(key, value)| (key, value))
+    }
+
+    /// Entry with the largest key, `None` if empty
+    pub fn last_key_value(&self) -> Option<(&K, &V)> {
+        self.entries.last().map(|(key, value)| (key, value))
+    }
+
+    /// Entries with keys within `range`, in ascending key order
+    pub fn range<R>(&self, range: R) -> FlatMapIter<'_, K, V>
+    where
+        R: RangeBounds<K>,
+    {
+        let start = match range.start_bound() {
+            Bound::Included(start) => self.entries.partition_point(|(key, _)| key < start),
+            Bound::Excluded(start) => self.entries.partition_point(|(key, _)| key <= start),
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(end) => self.entries.partition_point(|(key, _)| key <= end),
+            Bound::Excluded(end) => self.entries.partition_point(|(key, _)| key < end),
+            Bound::Unbounded => self.len(),
+        };
+
+        FlatMapIter {
+            inner: self.entries[start..end.max(start)].iter(),
+        }
+    }
+
+    /// Keeps only the entries for which `f` returns `true`
+    pub fn retain<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&K, &mut V) -> bool,
+    {
+        self.entries.retain_mut(|(key, value)| f(key, value));
+    }
+
+    /// Moves every entry of `other` into the map in a single linear pass, with values
+    /// from `other` replacing those of equal keys.
+    ///
+    /// Hands `other` back, with the map unchanged, if the result doesn't fit
+    pub fn merge<const M: usize>(
+        &mut self,
+        other: FlatMap<K, V, M>,
+    ) -> Result<(), FlatMap<K, V, M>> {
+        if union_len(&self.entries, &other.entries, |(a, _), (b, _)| a.cmp(b)) > N {
+            return Err(other);
+        }
+
+        let mine = mem::take(&mut self.entries);
+        let merged = MergeSorted::new(
+            mine.into_iter(),
+            other.entries.into_iter(),
+            |(a, _): &(K, V), (b, _): &(K, V)| a.cmp(b),
+        );
+        for entry in merged {
+            let pushed = self.entries.push(entry);
+            debug_assert!(pushed.is_ok());
+        }
+        Ok(())
+    }
+
+    /// Builds a map from an iterator, returning an error if it doesn't fit
+    pub fn try_from_iter<I>(iter: I) -> Result<Self, FlatMapError>
+    where
+        I: IntoIterator<Item = (K, V)>,
+    {
+        let mut map = Self::new();
+        map.try_extend(iter)?;
+        Ok(map)
+    }
+
+    /// Inserts every pair from an iterator, stopping at the first one that doesn't fit
+    pub fn try_extend<I>(&mut self, iter: I) -> Result<(), FlatMapError>
+    where
+        I: IntoIterator<Item = (K, V)>,
+    {
+        for (key, value) in iter {
+            self.insert(key, value)?;
+        }
+        Ok(())
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    fn search(&self, key: &K) -> Result<usize, usize> {
+        self.entries.binary_search_by(|(probe, _)| probe.cmp(key))
+    }
+}
+
+impl<K, V, const N: usize> FlatMap<K, V, N> {
+    /// Entries in ascending key order
+    pub fn iter(&self) -> FlatMapIter<'_, K, V> {
+        FlatMapIter {
+            inner: self.entries.as_slice().iter(),
+        }
+    }
+
+    /// Entries in ascending key order, with mutable values
+    pub fn iter_mut(&mut self) -> FlatMapIterMut<'_, K, V> {
+        FlatMapIterMut {
+            inner: self.entries.as_mut_slice().iter_mut(),
+        }
+    }
+
+    /// The entries as a slice sorted by key
+    pub fn as_slice(&self) -> &[(K, V)] {
+        self.entries.as_slice()
+    }
+}
+
+impl<K, V, const N: usize> Default for FlatMap<K, V, N>
+where
+    K: Ord,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Clone, V: Clone, const N: usize> Clone for FlatMap<K, V, N> {
+    fn clone(&self) -> Self {
+        Self {
+            entries: self.entries.clone(),
+        }
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug, const N: usize> fmt::Debug for FlatMap<K, V, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<K, V, const N: usize, const M: usize> PartialEq<FlatMap<K, V, M>> for FlatMap<K, V, N>
+where
+    K: PartialEq,
+    V: PartialEq,
+{
+    fn eq(&self, other: &FlatMap<K, V, M>) -> bool {
+        self.as_slice() == other.as_slice()
+    }
+}
+
+impl<K: Eq, V: Eq, const N: usize> Eq for FlatMap<K, V, N> {}
+
+impl<K: Hash, V: Hash, const N: usize> Hash for FlatMap<K, V, N> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_slice().hash(state);
+    }
+}
+
+impl<K, V, const N: usize> Index<&K> for FlatMap<K, V, N>
+where
+    K: Ord,
+{
+    type Output = V;
+
+    /// Panics if the key isn't present
+    fn index(&self, key: &K) -> &Self::Output {
+        self.get(key).expect("Key not found")
+    }
+}
+
+/// Panics if the iterator yields more pairs than the map can hold,
+/// use [`FlatMap::try_from_iter`] to handle that case.
+impl<K, V, const N: usize> FromIterator<(K, V)> for FlatMap<K, V, N>
+where
+    K: Ord,
+{
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        Self::try_from_iter(iter).expect("FlatMap capacity exceeded")
+    }
+}
+
+/// Panics if the map runs out of room, use [`FlatMap::try_extend`] to handle that case.
+impl<K, V, const N: usize> Extend<(K, V)> for FlatMap<K, V, N>
+where
+    K: Ord,
+{
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        self.try_extend(iter).expect("FlatMap capacity exceeded");
+    }
+}
+
+impl<K, V, const N: usize> IntoIterator for FlatMap<K, V, N> {
+    type Item = (K, V);
+    type IntoIter = FixedVecIntoIter<(K, V), N>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.into_iter()
+    }
+}
+
+impl<'a, K, V, const N: usize> IntoIterator for &'a FlatMap<K, V, N> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = FlatMapIter<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, K, V, const N: usize> IntoIterator for &'a mut FlatMap<K, V, N> {
+    type Item = (&'a K, &'a mut V);
+    type IntoIter = FlatMapIterMut<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+/// Iterator over entries in ascending key order, see [`FlatMap::iter`] and
+/// [`FlatMap::range`]
+pub struct FlatMapIter<'a, K, V> {
+    inner: slice::Iter<'a, (K, V)>,
+}
+
+impl<'a, K, V> Iterator for FlatMapIter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(key, value)| (key, value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<'a, K, V> DoubleEndedIterator for FlatMapIter<'a, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back().map(|(key, value)| (key, value))
+    }
+}
+
+impl<'a, K, V> ExactSizeIterator for FlatMapIter<'a, K, V> {
+    fn len(&self) -> usize {
+        self.inner.len()
+    }
+}
+
+impl<'a, K, V> FusedIterator for FlatMapIter<'a, K, V> {}
+
+pub struct FlatMapIterMut<'a, K, V> {
+    inner: slice::IterMut<'a, (K, V)>,
+}
+
+impl<'a, K, V> Iterator for FlatMapIterMut<'a, K, V> {
+    type Item = (&'a K, &'a mut V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(key, value)| (&*key, value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<'a, K, V> DoubleEndedIterator for FlatMapIterMut<'a, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back().map(|(key, value)| (&*key, value))
+    }
+}
+
+impl<'a, K, V> ExactSizeIterator for FlatMapIterMut<'a, K, V> {
+    fn len(&self) -> usize {
+        self.inner.len()
+    }
+}
+
+impl<'a, K, V> FusedIterator for FlatMapIterMut<'a, K, V> {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FlatMapError {
+    Full,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BTree;
+    use std::vec::Vec;
+
+    #[test]
+    fn test_insert_get_remove() {
+        let mut map = FlatMap::<u32, &str, 8>::new();
+        assert_eq!(map.insert(3, "c"), Ok(None));
+        assert_eq!(map.insert(1, "a"), Ok(None));
+        assert_eq!(map.insert(2, "b"), Ok(None));
+        assert_eq!(map.insert(3, "C"), Ok(Some("c")));
+
+        assert_eq!(map.len(), 3);
+        assert_eq!(map.get(&3), Some(&"C"));
+        assert_eq!(map.get(&4), None);
+        assert_eq!(map[&1], "a");
+        *map.get_mut(&2).unwrap() = "B";
+        assert_eq!(map.first_key_value(), Some((&1, &"a")));
+        assert_eq!(map.last_key_value(), Some((&3, &"C")));
+
+        assert_eq!(map.remove(&1), Some("a"));
+        assert_eq!(map.remove(&1), None);
+        assert_eq!(map.as_slice(), [(2, "B"), (3, "C")]);
+    }
+
+    #[test]
+    fn test_full() {
+        let mut map = FlatMap::<u8, u8, 2>::try_from_iter([(1, 1), (2, 2)]).unwrap();
+        assert_eq!(map.insert(3, 3), Err(FlatMapError::Full));
+        assert_eq!(map.insert(2, 20), Ok(Some(2)));
+        map.retain(|key, value| {
+            *value += 1;
+            *key == 2
+        });
+        assert_eq!(map.as_slice(), [(2, 21)]);
+    }
+
+    #[test]
+    fn test_range() {
+        let map: FlatMap<u32, u32, 16> = (0..10).map(|i| (i * 10, i)).collect();
+        let keys = |iter: FlatMapIter<'_, u32, u32>| iter.map(|(key, _)| *key).collect::<Vec<_>>();
+
+        assert_eq!(keys(map.range(20..50)), [20, 30, 40]);
+        assert_eq!(keys(map.range(..=10)), [0, 10]);
+        assert_eq!(keys(map.range(95..)), []);
+        assert_eq!(map.range(30..).next_back(), Some((&90, &9)));
+    }
+
+    #[test]
+    fn test_merge() {
+        let mut a = FlatMap::<u32, &str, 8>::try_from_iter([(1, "a"), (3, "c")]).unwrap();
+        let b = FlatMap::<u32, &str, 4>::try_from_iter([(2, "b"), (3, "C")]).unwrap();
+        a.merge(b).unwrap();
+        assert_eq!(a.as_slice(), [(1, "a"), (2, "b"), (3, "C")]);
+
+        let big: FlatMap<u32, &str, 8> = (10..16).map(|i| (i, "x")).collect();
+        let big = a.merge(big).unwrap_err();
+        assert_eq!(big.len(), 6);
+        assert_eq!(a.len(), 3);
+    }
+
+    #[test]
+    fn test_swap_for_btree() {
+        fn fill_flat(map: &mut FlatMap<u32, u32, 16>) {
+            for i in [5, 2, 8, 1] {
+                map.insert(i, i * i).unwrap();
+            }
+            map.remove(&2);
+        }
+
+        fn fill_tree(map: &mut BTree<u32, u32, 16>) {
+            for i in [5, 2, 8, 1] {
+                map.insert(i, i * i).unwrap();
+            }
+            map.remove(&2);
+        }
+
+        let mut flat = FlatMap::new();
+        let mut tree = BTree::new();
+        fill_flat(&mut flat);
+        fill_tree(&mut tree);
+
+        assert!(flat.iter().eq(tree.iter()));
+        assert_eq!(flat.contains_key(&8), tree.contains_key(&8));
+    }
+}
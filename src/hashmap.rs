@@ -1,27 +1,62 @@
+use core::fmt;
 use core::hash::{Hash, Hasher};
-use core::mem::MaybeUninit;
+use core::iter::{FusedIterator, Zip};
+use core::mem::{ManuallyDrop, MaybeUninit};
+use core::ops::{Deref, Index};
 use core::ptr;
+use core::slice;
+
+use crate::fixedvec::FixedVec;
+use crate::staticmap::StaticKey;
+
+/// Maximum load factor of a [`HashMap`], as a percentage of its capacity, unless
+/// `LOAD` says otherwise
+pub const DEFAULT_LOAD: usize = 75;
 
 /// HashMap implementation designed to be cache-friendly,
 /// using open addressing and linear probing.
 ///
-/// Capacity must be a power of 2.
-pub struct HashMap<K, V, const N: usize> {
-    buckets: [MaybeUninit<Bucket<K, V>>; N],
+/// Capacity must be a power of 2. `LOAD` is the maximum load factor as a percentage
+/// of `N` (75% by default), and must leave at least one bucket empty.
+pub struct HashMap<K, V, const N: usize, const LOAD: usize = DEFAULT_LOAD> {
+    buckets: [Bucket<K, V>; N],
+    states: [BucketState; N],
     len: usize,
 }
 
-impl<K, V, const N: usize> HashMap<K, V, N>
+impl<K, V, const N: usize, const LOAD: usize> HashMap<K, V, N, LOAD>
 where
     K: Hash + PartialEq,
 {
+    const LOAD_CHECK: () = {
+        assert!(LOAD > 0, "HashMap load factor must be greater than 0");
+        assert!(LOAD < 100, "HashMap load factor must leave a bucket empty");
+    };
+
     /// Panics if `N` is not a power of 2 (or is 0)
+    ///
+    /// Fails to compile if `LOAD` isn't between 1 and 99, so a full map always keeps
+    /// an empty bucket:
+    ///
+    /// ```compile_fail
+    /// use sakurai::HashMap;
+    ///
+    /// let map = HashMap::<u8, u8, 8, 100>::new();
+    /// ```
+    ///
+    /// ```compile_fail
+    /// use sakurai::HashMap;
+    ///
+    /// let map = HashMap::<u8, u8, 8, 0>::new();
+    /// ```
     pub const fn new() -> Self {
         assert!(N > 0, "HashMap size must be greater than 0");
         assert!(N.is_power_of_two(), "HashMap size must be a power of 2");
+        let () = Self::LOAD_CHECK;
 
         Self {
-            buckets: unsafe { MaybeUninit::uninit().assume_init() },
+            buckets: [const { Bucket::new() }; N],
+            states: [BucketState::Empty; N],
             len: 0,
         }
     }
@@ -41,9 +76,15 @@ where
         self.len == 0
     }
 
+    /// Maximum number of entries the map accepts, `N * LOAD / 100` rounded down
+    #[inline]
+    pub const fn max_len(&self) -> usize {
+        load_limit(N, LOAD)
+    }
+
     #[inline]
     pub const fn is_full(&self) -> bool {
-        self.len >= N * 3 / 4
+        self.len >= self.max_len()
     }
 
     #[inline]
@@ -51,27 +92,83 @@ where
         self.len as f32 / N as f32
     }
 
-    /// Inserts a key-value pair into the map, returning the old value if the key already exists
+    /// Measures how far entries sit from their home buckets, for tuning `N`.
+    ///
+    /// A probe distance of 0 means the entry is in its home bucket. Bucket `d` of
+    /// `histogram` is overwritten with the number of entries at distance `d`, and the
+    /// last bucket also counts every entry further away than that.
+    pub fn probe_stats(&self, histogram: &mut [usize]) -> ProbeStats {
+        histogram.fill(0);
+
+        let mut stats = ProbeStats::default();
+        let mut total = 0;
+        let mut run = 0;
+        let mut leading_run = None;
+
+        for index in 0..N {
+            match self.states[index] {
+                BucketState::Occupied => {
+                    let key = unsafe { self.buckets[index].key.assume_init_ref() };
+                    let distance = probe_distance::<N>(self.hash_key(key), index);
+
+                    stats.max_probe = stats.max_probe.max(distance);
+                    total += distance;
+                    if let Some(last) = histogram.len().checked_sub(1) {
+                        histogram[distance.min(last)] += 1;
+                    }
+
+                    run += 1;
+                    continue;
+                }
+                BucketState::Deleted => stats.tombstones += 1,
+                BucketState::Empty => {}
+            }
+
+            leading_run.get_or_insert(run);
+            stats.longest_cluster = stats.longest_cluster.max(run);
+            run = 0;
+        }
+
+        // the run at the end of the table wraps around into the one at the start
+        let wrapped = run + leading_run.unwrap_or(0);
+        stats.longest_cluster = stats.longest_cluster.max(wrapped.min(self.len));
+
+        if self.len > 0 {
+            stats.mean_probe = total as f32 / self.len as f32;
+        }
+        stats
+    }
+
+    /// Inserts a key-value pair into the map, returning the old value if the key already exists.
+    ///
+    /// Replacing the value of a key that's already present succeeds even when the map is
+    /// full, only a new key fails with [`HashMapError::Full`].
     pub fn insert(&mut self, key: K, value: V) -> Result<Option<V>, HashMapError> {
+        self.try_insert(key, value).map_err(|_| HashMapError::Full)
+    }
+
+    /// Attempts to insert a key-value pair, returning the pair if the map is full.
+    ///
+    /// Replacing the value of a key that's already present always succeeds.
+    pub fn try_insert(&mut self, key: K, value: V) -> Result<Option<V>, (K, V)> {
         if self.is_full() {
-            return Err(HashMapError::Full);
+            return match self.get_mut(&key) {
+                Some(slot) => Ok(Some(core::mem::replace(slot, value))),
+                None => Err((key, value)),
+            };
         }
 
         let (index, found) = self.find_bucket(&key);
-        let bucket = unsafe { &mut *self.buckets[index].as_mut_ptr() };
+        let bucket = &mut self.buckets[index];
 
         if found {
             let old_value = unsafe { ptr::read(bucket.value.as_ptr()) };
-            unsafe {
-                ptr::write(bucket.value.as_mut_ptr(), value);
-            }
+            bucket.value.write(value);
             Ok(Some(old_value))
         } else {
-            unsafe {
-                ptr::write(bucket.key.as_mut_ptr(), key);
-                ptr::write(bucket.value.as_mut_ptr(), value);
-            }
-            bucket.state = BucketState::Occupied;
+            bucket.key.write(key);
+            bucket.value.write(value);
+            self.states[index] = BucketState::Occupied;
             self.len += 1;
             Ok(None)
         }
@@ -79,116 +176,342 @@ where
 
     /// Get a reference to a value for a given key
     pub fn get(&self, key: &K) -> Option<&V> {
-        self.find_bucket_ro(key).map(|index| {
-            let bucket = unsafe { &*self.buckets[index].as_ptr() };
-            unsafe { &*bucket.value.as_ptr() }
-        })
+        self.find_bucket_ro(key)
+            .map(|index| unsafe { self.buckets[index].value.assume_init_ref() })
     }
 
     /// Get a mut reference to a value for a given key
     pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        self.find_bucket_ro(key)
+            .map(|index| unsafe { self.buckets[index].value.assume_init_mut() })
+    }
+
+    /// Get mut references to the values for several keys at once.
+    ///
+    /// Returns `None` if any key is missing, or if two of the keys are equal
+    pub fn get_many_mut<const M: usize>(&mut self, keys: [&K; M]) -> Option<[&mut V; M]> {
+        let indices = self.find_many(keys)?;
+
+        for (i, index) in indices.iter().enumerate() {
+            if indices[..i].contains(index) {
+                return None;
+            }
+        }
+
+        Some(unsafe { self.values_at_mut(indices) })
+    }
+
+    /// Get mut references to the values for several keys at once, without checking
+    /// that the keys are distinct.
+    ///
+    /// Returns `None` if any key is missing.
+    ///
+    /// # Safety
+    ///
+    /// No two keys may be equal, otherwise the returned references alias
+    pub unsafe fn get_many_unchecked_mut<const M: usize>(
+        &mut self,
+        keys: [&K; M],
+    ) -> Option<[&mut V; M]> {
+        let indices = self.find_many(keys)?;
+        Some(unsafe { self.values_at_mut(indices) })
+    }
+
+    /// Get references to the stored key and its value for a given key
+    pub fn get_key_value(&self, key: &K) -> Option<(&K, &V)> {
         self.find_bucket_ro(key).map(|index| {
-            let bucket = unsafe { &mut *self.buckets[index].as_mut_ptr() };
-            unsafe { &mut *bucket.value.as_mut_ptr() }
+            let bucket = &self.buckets[index];
+            unsafe { (bucket.key.assume_init_ref(), bucket.value.assume_init_ref()) }
         })
     }
 
     /// Remove a key-value pair from the map, returning the value if the key was present.
     pub fn remove(&mut self, key: &K) -> Option<V> {
-        self.find_bucket_ro(key).map(|index| {
-            let bucket = unsafe { &mut *self.buckets[index].as_mut_ptr() };
-
-            let value = unsafe { ptr::read(bucket.value.as_ptr()) };
-            unsafe {
-                ptr::drop_in_place(bucket.key.as_mut_ptr());
-            }
+        self.remove_entry(key).map(|(_, value)| value)
+    }
 
-            bucket.state = BucketState::Deleted;
-            self.len -= 1;
+    /// Remove a key-value pair from the map, returning the stored key and value if the key was present.
+    pub fn remove_entry(&mut self, key: &K) -> Option<(K, V)> {
+        self.find_bucket_ro(key)
+            .map(|index| unsafe { self.take_bucket(index) })
+    }
 
-            value
-        })
+    /// Swaps the stored key for an equal `key`, returning the old one if the key was present
+    pub(crate) fn replace_key(&mut self, key: K) -> Result<K, K> {
+        match self.find_bucket_ro(&key) {
+            Some(index) => {
+                let slot = unsafe { self.buckets[index].key.assume_init_mut() };
+                Ok(core::mem::replace(slot, key))
+            }
+            None => Err(key),
+        }
     }
 
     pub fn contains_key(&self, key: &K) -> bool {
         self.get(key).is_some()
     }
 
-    pub fn clear(&mut self) {
-        for i in 0..N {
-            let bucket = unsafe { &mut *self.buckets[i].as_mut_ptr() };
-            if bucket.is_occupied() {
-                unsafe {
-                    ptr::drop_in_place(bucket.key.as_mut_ptr());
-                    ptr::drop_in_place(bucket.value.as_mut_ptr());
+    /// Builds a map from key-value pairs, usually through [`hashmap!`](crate::hashmap).
+    /// Later pairs replace earlier ones with the same key.
+    ///
+    /// It's a `const fn`, so the map can be built in a `static` or `const`, which
+    /// limits it to [`StaticKey`] keys. Other keys can go through [`From`] instead.
+    ///
+    /// Fails to compile if there are more pairs than [`max_len`](Self::max_len)
+    pub const fn from_array<const M: usize>(entries: [(K, V); M]) -> Self
+    where
+        K: const StaticKey + const PartialEq,
+    {
+        let () = Fits::<M, N, LOAD>::CHECK;
+
+        // pairs still in the array are leaked rather than dropped twice if a destructor
+        // panics below
+        let entries = ManuallyDrop::new(entries);
+        let pairs = (&raw const entries).cast::<(K, V)>();
+
+        let mut map = Self::new();
+        let mut i = 0;
+        while i < M {
+            let pair = unsafe { pairs.add(i) };
+            let key = unsafe { ptr::read(&raw const (*pair).0) };
+            let value = unsafe { ptr::read(&raw const (*pair).1) };
+            let mut probe = Probe::<N>::new(key.static_hash() as usize);
+
+            loop {
+                // the load factor always leaves an empty bucket
+                let Some(index) = probe.next_index() else {
+                    panic!("HashMap has no free bucket");
+                };
+
+                if !matches!(map.states[index], BucketState::Occupied) {
+                    map.buckets[index].key.write(key);
+                    map.buckets[index].value.write(value);
+                    map.states[index] = BucketState::Occupied;
+                    map.len += 1;
+                    break;
+                }
+
+                let bucket = &mut map.buckets[index];
+                if unsafe { bucket.key.assume_init_ref() } == &key {
+                    let old_value = unsafe { ptr::replace(bucket.value.as_mut_ptr(), value) };
+                    crate::discard(old_value);
+                    crate::discard(key);
+                    break;
                 }
             }
-            bucket.state = BucketState::Empty;
+            i += 1;
+        }
+        map
+    }
+
+    /// Builds a map from an iterator, returning an error if it doesn't fit
+    pub fn try_from_iter<I>(iter: I) -> Result<Self, HashMapError>
+    where
+        I: IntoIterator<Item = (K, V)>,
+    {
+        let mut map = Self::new();
+        map.try_extend(iter)?;
+        Ok(map)
+    }
+
+    /// Inserts every pair from an iterator, stopping at the first one that doesn't fit
+    pub fn try_extend<I>(&mut self, iter: I) -> Result<(), HashMapError>
+    where
+        I: IntoIterator<Item = (K, V)>,
+    {
+        for (key, value) in iter {
+            self.insert(key, value)?;
         }
+        Ok(())
+    }
+
+    pub fn clear(&mut self) {
+        self.drop_occupied();
+        self.states = [BucketState::Empty; N];
         self.len = 0;
     }
 
-    pub fn iter(&self) -> HashMapIter<'_, K, V, N> {
-        HashMapIter {
+    /// Starts a transaction that records up to `L` bucket changes, so they can all be
+    /// undone by [`rollback`](HashMapTransaction::rollback) or by dropping the guard
+    pub fn begin<const L: usize>(&mut self) -> HashMapTransaction<'_, K, V, N, LOAD, L> {
+        HashMapTransaction {
+            len: self.len,
             map: self,
-            index: 0,
+            log: FixedVec::new(),
         }
     }
 
     fn hash_key(&self, key: &K) -> usize {
-        let mut hasher = Fnv1aHasher::new();
-        key.hash(&mut hasher);
-        (hasher.finish() as usize) & (N - 1)
+        (fnv1a_hash(key) as usize) & (N - 1)
     }
 
+    /// Returns the bucket holding `key`, or the bucket it should be inserted into.
+    ///
+    /// Deleted buckets are reused, but only once the whole probe sequence has been
+    /// checked for the key itself. Callers must make sure the map is not full.
     fn find_bucket(&self, key: &K) -> (usize, bool) {
-        let mut index = self.hash_key(key);
+        let mut tombstone = None;
 
-        loop {
-            let bucket = unsafe { &*self.buckets[index].as_ptr() };
-
-            match bucket.state {
-                BucketState::Empty => return (index, false),
+        for index in Probe::<N>::new(self.hash_key(key)) {
+            match self.states[index] {
+                BucketState::Empty => return (tombstone.unwrap_or(index), false),
                 BucketState::Occupied => {
-                    let bucket_key = unsafe { &*bucket.key.as_ptr() };
+                    let bucket_key = unsafe { self.buckets[index].key.assume_init_ref() };
                     if bucket_key == key {
                         return (index, true);
                     }
                 }
-                BucketState::Deleted => {}
+                BucketState::Deleted => {
+                    tombstone.get_or_insert(index);
+                }
             }
-
-            index = (index + 1) & (N - 1);
         }
+
+        // every bucket is occupied or deleted, and `is_full` guarantees a deleted one
+        (tombstone.expect("HashMap has no free bucket"), false)
     }
 
     fn find_bucket_ro(&self, key: &K) -> Option<usize> {
-        let mut index = self.hash_key(key);
-
-        loop {
-            let bucket = unsafe { &*self.buckets[index].as_ptr() };
-
-            match bucket.state {
+        for index in Probe::<N>::new(self.hash_key(key)) {
+            match self.states[index] {
                 BucketState::Empty => return None,
                 BucketState::Occupied => {
-                    let bucket_key = unsafe { &*bucket.key.as_ptr() };
+                    let bucket_key = unsafe { self.buckets[index].key.assume_init_ref() };
                     if bucket_key == key {
                         return Some(index);
                     }
                 }
                 BucketState::Deleted => {}
             }
+        }
 
-            index = (index + 1) & (N - 1);
+        None
+    }
 
-            if index == self.hash_key(key) {
-                return None;
+    fn find_many<const M: usize>(&self, keys: [&K; M]) -> Option<[usize; M]> {
+        let mut indices = [0; M];
+        for (index, key) in indices.iter_mut().zip(keys) {
+            *index = self.find_bucket_ro(key)?;
+        }
+        Some(indices)
+    }
+}
+
+impl<K, V, const N: usize, const LOAD: usize> HashMap<K, V, N, LOAD> {
+    pub fn iter(&self) -> HashMapIter<'_, K, V, N> {
+        HashMapIter {
+            inner: self.buckets.iter().zip(self.states.iter()),
+            remaining: self.len,
+        }
+    }
+
+    pub fn iter_mut(&mut self) -> HashMapIterMut<'_, K, V, N> {
+        HashMapIterMut {
+            inner: self.buckets.iter_mut().zip(self.states.iter()),
+            remaining: self.len,
+        }
+    }
+
+    pub fn keys(&self) -> HashMapKeys<'_, K, V, N> {
+        HashMapKeys { inner: self.iter() }
+    }
+
+    pub fn values(&self) -> HashMapValues<'_, K, V, N> {
+        HashMapValues { inner: self.iter() }
+    }
+
+    pub fn values_mut(&mut self) -> HashMapValuesMut<'_, K, V, N> {
+        HashMapValuesMut {
+            inner: self.iter_mut(),
+        }
+    }
+
+    /// Removes every key-value pair from the map, yielding them as it goes.
+    ///
+    /// Pairs that haven't been yielded when the iterator is dropped are dropped with it.
+    /// If the iterator is leaked, the map keeps whatever wasn't yielded.
+    pub fn drain(&mut self) -> HashMapDrain<'_, K, V, N, LOAD> {
+        HashMapDrain {
+            map: self,
+            index: 0,
+        }
+    }
+
+    /// Keeps only the pairs for which `f` returns `true`
+    pub fn retain<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&K, &mut V) -> bool,
+    {
+        for i in 0..N {
+            if !self.states[i].is_occupied() {
+                continue;
+            }
+
+            let bucket = &mut self.buckets[i];
+            let keep = unsafe { f(bucket.key.assume_init_ref(), bucket.value.assume_init_mut()) };
+            if !keep {
+                drop(unsafe { self.take_bucket(i) });
+            }
+        }
+    }
+
+    /// Returns an iterator that removes and yields every pair for which `pred` returns `true`.
+    ///
+    /// Pairs that haven't been visited when the iterator is dropped stay in the map.
+    pub fn extract_if<F>(&mut self, pred: F) -> HashMapExtractIf<'_, K, V, F, N, LOAD>
+    where
+        F: FnMut(&K, &mut V) -> bool,
+    {
+        HashMapExtractIf {
+            map: self,
+            index: 0,
+            pred,
+        }
+    }
+
+    /// Moves the pair out of an occupied bucket, marking it deleted
+    ///
+    /// # Safety
+    ///
+    /// The bucket at `index` must be occupied
+    unsafe fn take_bucket(&mut self, index: usize) -> (K, V) {
+        self.states[index] = BucketState::Deleted;
+        self.len -= 1;
+
+        let bucket = &self.buckets[index];
+        unsafe {
+            (
+                ptr::read(bucket.key.as_ptr()),
+                ptr::read(bucket.value.as_ptr()),
+            )
+        }
+    }
+
+    /// # Safety
+    ///
+    /// Every bucket in `indices` must be occupied, and no index may appear twice
+    unsafe fn values_at_mut<const M: usize>(&mut self, indices: [usize; M]) -> [&mut V; M] {
+        let buckets = self.buckets.as_mut_ptr();
+        indices.map(|index| unsafe { (*buckets.add(index)).value.assume_init_mut() })
+    }
+
+    /// Drops every occupied key and value, leaving the metadata untouched
+    fn drop_occupied(&mut self) {
+        for i in 0..N {
+            if self.states[i].is_occupied() {
+                // clear the state first so a panicking destructor can't cause a double drop
+                self.states[i] = BucketState::Deleted;
+                let bucket = &mut self.buckets[i];
+                unsafe {
+                    bucket.key.assume_init_drop();
+                    bucket.value.assume_init_drop();
+                }
             }
         }
     }
 }
 
-impl<K, V, const N: usize> Default for HashMap<K, V, N>
+impl<K, V, const N: usize, const LOAD: usize> Default for HashMap<K, V, N, LOAD>
 where
     K: Hash + PartialEq,
 {
@@ -197,37 +520,212 @@ where
     }
 }
 
-impl<K, V, const N: usize> Drop for HashMap<K, V, N> {
+impl<K, V, const N: usize, const LOAD: usize> Drop for HashMap<K, V, N, LOAD> {
     fn drop(&mut self) {
+        self.drop_occupied();
+    }
+}
+
+impl<K, V, const N: usize, const LOAD: usize> Clone for HashMap<K, V, N, LOAD>
+where
+    K: Clone,
+    V: Clone,
+{
+    /// Copies the table bucket for bucket, so the clone probes exactly like the original
+    fn clone(&self) -> Self {
+        let mut map = Self {
+            buckets: [const { Bucket::new() }; N],
+            states: [BucketState::Empty; N],
+            len: 0,
+        };
+
         for i in 0..N {
-            let bucket = unsafe { &mut *self.buckets[i].as_mut_ptr() };
-            if bucket.is_occupied() {
-                unsafe {
-                    ptr::drop_in_place(bucket.key.as_mut_ptr());
-                    ptr::drop_in_place(bucket.value.as_mut_ptr());
-                }
+            if self.states[i].is_occupied() {
+                let bucket = &self.buckets[i];
+                let key = unsafe { bucket.key.assume_init_ref() }.clone();
+                let value = unsafe { bucket.value.assume_init_ref() }.clone();
+
+                map.buckets[i].key.write(key);
+                map.buckets[i].value.write(value);
+                map.len += 1;
             }
-            bucket.state = BucketState::Empty;
+            map.states[i] = self.states[i];
+        }
+        map
+    }
+}
+
+impl<K, V, const N: usize, const LOAD: usize> fmt::Debug for HashMap<K, V, N, LOAD>
+where
+    K: fmt::Debug,
+    V: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<K, V, const N: usize, const LOAD: usize> PartialEq for HashMap<K, V, N, LOAD>
+where
+    K: Hash + PartialEq,
+    V: PartialEq,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.len == other.len
+            && self
+                .iter()
+                .all(|(key, value)| other.get(key) == Some(value))
+    }
+}
+
+impl<K, V, const N: usize, const LOAD: usize> Eq for HashMap<K, V, N, LOAD>
+where
+    K: Hash + Eq,
+    V: Eq,
+{
+}
+
+impl<K, V, const N: usize, const LOAD: usize> Index<&K> for HashMap<K, V, N, LOAD>
+where
+    K: Hash + PartialEq,
+{
+    type Output = V;
+
+    /// Panics if the key isn't present
+    fn index(&self, key: &K) -> &Self::Output {
+        self.get(key).expect("Key not found")
+    }
+}
+
+/// Fails to compile if the array has more pairs than the map can hold
+impl<K, V, const N: usize, const LOAD: usize, const M: usize> From<[(K, V); M]>
+    for HashMap<K, V, N, LOAD>
+where
+    K: Hash + PartialEq,
+{
+    /// Later pairs replace earlier ones with the same key
+    fn from(entries: [(K, V); M]) -> Self {
+        let () = Fits::<M, N, LOAD>::CHECK;
+
+        let mut map = Self::new();
+        for (key, value) in entries {
+            let inserted = map.try_insert(key, value);
+            debug_assert!(inserted.is_ok());
+        }
+        map
+    }
+}
+
+/// Compile-time check that `M` entries fit under the load factor
+struct Fits<const M: usize, const N: usize, const LOAD: usize>;
+
+impl<const M: usize, const N: usize, const LOAD: usize> Fits<M, N, LOAD> {
+    const CHECK: () = assert!(
+        M <= load_limit(N, LOAD),
+        "too many entries for the HashMap's capacity"
+    );
+}
+
+/// Builds a [`HashMap`](crate::HashMap) from `key => value` pairs. The capacity comes
+/// from the type being built, and more pairs than its load factor allows is a compile
+/// error.
+///
+/// It builds through the `const fn` [`HashMap::from_array`], so it works in `static`
+/// and `const` items, for keys that implement [`StaticKey`]:
+///
+/// ```
+/// use sakurai::{HashMap, hashmap};
+///
+/// static ROUTES: HashMap<&str, u16, 8> = hashmap! {
+///     "api" => 8080,
+///     "web" => 80,
+/// };
+///
+/// assert_eq!(ROUTES.get(&"api"), Some(&8080));
+/// ```
+///
+/// ```compile_fail
+/// use sakurai::{HashMap, hashmap};
+///
+/// // 4 buckets at the default 75% load hold 3 entries
+/// let map: HashMap<u8, u8, 4> = hashmap! { 1 => 1, 2 => 2, 3 => 3, 4 => 4 };
+/// ```
+#[macro_export]
+macro_rules! hashmap {
+    ($($key:expr => $value:expr),* $(,)?) => {
+        $crate::hashmap::HashMap::from_array([$(($key, $value)),*])
+    };
+}
+
+/// Panics if the iterator yields more pairs than the map can hold,
+/// use [`HashMap::try_from_iter`] to handle that case.
+impl<K, V, const N: usize, const LOAD: usize> FromIterator<(K, V)> for HashMap<K, V, N, LOAD>
+where
+    K: Hash + PartialEq,
+{
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        Self::try_from_iter(iter).expect("HashMap capacity exceeded")
+    }
+}
+
+/// Panics if the map runs out of room, use [`HashMap::try_extend`] to handle that case.
+impl<K, V, const N: usize, const LOAD: usize> Extend<(K, V)> for HashMap<K, V, N, LOAD>
+where
+    K: Hash + PartialEq,
+{
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        self.try_extend(iter).expect("HashMap capacity exceeded");
+    }
+}
+
+impl<'a, K, V, const N: usize, const LOAD: usize> IntoIterator for &'a HashMap<K, V, N, LOAD> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = HashMapIter<'a, K, V, N>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, K, V, const N: usize, const LOAD: usize> IntoIterator for &'a mut HashMap<K, V, N, LOAD> {
+    type Item = (&'a K, &'a mut V);
+    type IntoIter = HashMapIterMut<'a, K, V, N>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+impl<K, V, const N: usize, const LOAD: usize> IntoIterator for HashMap<K, V, N, LOAD> {
+    type Item = (K, V);
+    type IntoIter = HashMapIntoIter<K, V, N, LOAD>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        HashMapIntoIter {
+            map: self,
+            index: 0,
         }
     }
 }
 
 pub struct HashMapIter<'a, K, V, const N: usize> {
-    map: &'a HashMap<K, V, N>,
-    index: usize,
+    inner: Zip<slice::Iter<'a, Bucket<K, V>>, slice::Iter<'a, BucketState>>,
+    remaining: usize,
 }
 
 impl<'a, K, V, const N: usize> Iterator for HashMapIter<'a, K, V, N> {
     type Item = (&'a K, &'a V);
 
     fn next(&mut self) -> Option<Self::Item> {
-        while self.index < N {
-            let bucket = unsafe { &*self.map.buckets[self.index].as_ptr() };
-            self.index += 1;
+        if self.remaining == 0 {
+            return None;
+        }
 
-            if bucket.is_occupied() {
-                let key = unsafe { &*bucket.key.as_ptr() };
-                let value = unsafe { &*bucket.value.as_ptr() };
+        for (bucket, state) in self.inner.by_ref() {
+            if state.is_occupied() {
+                self.remaining -= 1;
+                let key = unsafe { bucket.key.assume_init_ref() };
+                let value = unsafe { bucket.value.assume_init_ref() };
                 return Some((key, value));
             }
         }
@@ -235,81 +733,580 @@ impl<'a, K, V, const N: usize> Iterator for HashMapIter<'a, K, V, N> {
     }
 
     fn size_hint(&self) -> (usize, Option<usize>) {
-        (self.map.len, Some(self.map.len))
+        (self.remaining, Some(self.remaining))
     }
 }
 
-#[derive(Clone, Copy)]
-enum BucketState {
-    Empty,
-    Occupied,
-    Deleted,
+impl<'a, K, V, const N: usize> ExactSizeIterator for HashMapIter<'a, K, V, N> {
+    fn len(&self) -> usize {
+        self.remaining
+    }
 }
 
-struct Bucket<K, V> {
-    state: BucketState,
-    key: MaybeUninit<K>,
-    value: MaybeUninit<V>,
+impl<'a, K, V, const N: usize> FusedIterator for HashMapIter<'a, K, V, N> {}
+
+/// Mutable iterator over map entries, keys are still shared
+pub struct HashMapIterMut<'a, K, V, const N: usize> {
+    inner: Zip<slice::IterMut<'a, Bucket<K, V>>, slice::Iter<'a, BucketState>>,
+    remaining: usize,
 }
 
-impl<K, V> Bucket<K, V> {
-    #[allow(unused)]
-    const fn new() -> Self {
-        Self {
-            state: BucketState::Empty,
-            key: MaybeUninit::uninit(),
-            value: MaybeUninit::uninit(),
+impl<'a, K, V, const N: usize> Iterator for HashMapIterMut<'a, K, V, N> {
+    type Item = (&'a K, &'a mut V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+
+        for (bucket, state) in self.inner.by_ref() {
+            if state.is_occupied() {
+                self.remaining -= 1;
+                let key = unsafe { bucket.key.assume_init_ref() };
+                let value = unsafe { bucket.value.assume_init_mut() };
+                return Some((key, value));
+            }
         }
+        None
     }
 
-    #[inline]
-    fn is_occupied(&self) -> bool {
-        matches!(self.state, BucketState::Occupied)
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
     }
 }
 
-/// A dead-simple (and fast, of course) hash function based on FNV-1a
-struct Fnv1aHasher {
-    state: u64,
+impl<'a, K, V, const N: usize> ExactSizeIterator for HashMapIterMut<'a, K, V, N> {
+    fn len(&self) -> usize {
+        self.remaining
+    }
 }
 
-impl Fnv1aHasher {
-    const fn new() -> Self {
-        Self {
-            state: 0xcbf29ce484222325,
-        }
-    }
+impl<'a, K, V, const N: usize> FusedIterator for HashMapIterMut<'a, K, V, N> {}
+
+pub struct HashMapKeys<'a, K, V, const N: usize> {
+    inner: HashMapIter<'a, K, V, N>,
 }
 
-impl Hasher for Fnv1aHasher {
-    fn finish(&self) -> u64 {
-        self.state
+impl<'a, K, V, const N: usize> Iterator for HashMapKeys<'a, K, V, N> {
+    type Item = &'a K;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(key, _)| key)
     }
 
-    fn write(&mut self, bytes: &[u8]) {
-        const FNV_PRIME: u64 = 0x100000001b3;
-        for &byte in bytes {
-            self.state ^= byte as u64;
-            self.state = self.state.wrapping_mul(FNV_PRIME);
-        }
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum HashMapError {
-    Full,
-    NotFound,
+impl<'a, K, V, const N: usize> ExactSizeIterator for HashMapKeys<'a, K, V, N> {
+    fn len(&self) -> usize {
+        self.inner.len()
+    }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::format;
-    use std::string::{String, ToString};
+impl<'a, K, V, const N: usize> FusedIterator for HashMapKeys<'a, K, V, N> {}
 
-    #[test]
-    fn test_access() {
-        let mut map = HashMap::<u32, String, 8>::new();
+pub struct HashMapValues<'a, K, V, const N: usize> {
+    inner: HashMapIter<'a, K, V, N>,
+}
+
+impl<'a, K, V, const N: usize> Iterator for HashMapValues<'a, K, V, N> {
+    type Item = &'a V;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(_, value)| value)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<'a, K, V, const N: usize> ExactSizeIterator for HashMapValues<'a, K, V, N> {
+    fn len(&self) -> usize {
+        self.inner.len()
+    }
+}
+
+impl<'a, K, V, const N: usize> FusedIterator for HashMapValues<'a, K, V, N> {}
+
+pub struct HashMapValuesMut<'a, K, V, const N: usize> {
+    inner: HashMapIterMut<'a, K, V, N>,
+}
+
+impl<'a, K, V, const N: usize> Iterator for HashMapValuesMut<'a, K, V, N> {
+    type Item = &'a mut V;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(_, value)| value)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<'a, K, V, const N: usize> ExactSizeIterator for HashMapValuesMut<'a, K, V, N> {
+    fn len(&self) -> usize {
+        self.inner.len()
+    }
+}
+
+impl<'a, K, V, const N: usize> FusedIterator for HashMapValuesMut<'a, K, V, N> {}
+
+/// Owning iterator over map entries, whatever isn't consumed is dropped with it
+pub struct HashMapIntoIter<K, V, const N: usize, const LOAD: usize = DEFAULT_LOAD> {
+    map: HashMap<K, V, N, LOAD>,
+    index: usize,
+}
+
+impl<K, V, const N: usize, const LOAD: usize> Iterator for HashMapIntoIter<K, V, N, LOAD> {
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.index < N {
+            let index = self.index;
+            self.index += 1;
+
+            if self.map.states[index].is_occupied() {
+                return Some(unsafe { self.map.take_bucket(index) });
+            }
+        }
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.map.len, Some(self.map.len))
+    }
+}
+
+impl<K, V, const N: usize, const LOAD: usize> ExactSizeIterator for HashMapIntoIter<K, V, N, LOAD> {
+    fn len(&self) -> usize {
+        self.map.len
+    }
+}
+
+impl<K, V, const N: usize, const LOAD: usize> FusedIterator for HashMapIntoIter<K, V, N, LOAD> {}
+
+/// Draining iterator, see [`HashMap::drain`]
+pub struct HashMapDrain<'a, K, V, const N: usize, const LOAD: usize = DEFAULT_LOAD> {
+    map: &'a mut HashMap<K, V, N, LOAD>,
+    index: usize,
+}
+
+impl<'a, K, V, const N: usize, const LOAD: usize> Iterator for HashMapDrain<'a, K, V, N, LOAD> {
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.index < N {
+            let index = self.index;
+            self.index += 1;
+
+            if self.map.states[index].is_occupied() {
+                return Some(unsafe { self.map.take_bucket(index) });
+            }
+        }
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.map.len, Some(self.map.len))
+    }
+}
+
+impl<'a, K, V, const N: usize, const LOAD: usize> ExactSizeIterator
+    for HashMapDrain<'a, K, V, N, LOAD>
+{
+    fn len(&self) -> usize {
+        self.map.len
+    }
+}
+
+impl<'a, K, V, const N: usize, const LOAD: usize> FusedIterator
+    for HashMapDrain<'a, K, V, N, LOAD>
+{
+}
+
+impl<'a, K, V, const N: usize, const LOAD: usize> Drop for HashMapDrain<'a, K, V, N, LOAD> {
+    fn drop(&mut self) {
+        // every pair is unlinked before it's dropped, so if a destructor panics
+        // the map still holds exactly the pairs that haven't been reached yet
+        self.by_ref().for_each(drop);
+
+        // nothing is left, so the tombstones can go too
+        self.map.states = [BucketState::Empty; N];
+    }
+}
+
+/// Iterator returned by [`HashMap::extract_if`]
+pub struct HashMapExtractIf<'a, K, V, F, const N: usize, const LOAD: usize = DEFAULT_LOAD>
+where
+    F: FnMut(&K, &mut V) -> bool,
+{
+    map: &'a mut HashMap<K, V, N, LOAD>,
+    index: usize,
+    pred: F,
+}
+
+impl<'a, K, V, F, const N: usize, const LOAD: usize> Iterator
+    for HashMapExtractIf<'a, K, V, F, N, LOAD>
+where
+    F: FnMut(&K, &mut V) -> bool,
+{
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.index < N {
+            let index = self.index;
+            self.index += 1;
+
+            if !self.map.states[index].is_occupied() {
+                continue;
+            }
+
+            let bucket = &mut self.map.buckets[index];
+            let extract = unsafe {
+                (self.pred)(bucket.key.assume_init_ref(), bucket.value.assume_init_mut())
+            };
+            if extract {
+                return Some(unsafe { self.map.take_bucket(index) });
+            }
+        }
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.map.len))
+    }
+}
+
+impl<'a, K, V, F, const N: usize, const LOAD: usize> FusedIterator
+    for HashMapExtractIf<'a, K, V, F, N, LOAD>
+where
+    F: FnMut(&K, &mut V) -> bool,
+{
+}
+
+/// Guard returned by [`HashMap::begin`].
+///
+/// Every change goes through the guard, which saves the prior state of each bucket it
+/// touches in an inline undo log of `L` entries. Replaced values and removed pairs are
+/// kept in the log, so they're only dropped on [`commit`](Self::commit). A change that
+/// would overflow the log fails with [`HashMapError::UndoLogFull`] without being applied.
+pub struct HashMapTransaction<'a, K, V, const N: usize, const LOAD: usize, const L: usize> {
+    map: &'a mut HashMap<K, V, N, LOAD>,
+    log: FixedVec<Undo<K, V>, L>,
+    len: usize,
+}
+
+/// Prior state of a bucket changed during a transaction
+enum Undo<K, V> {
+    /// A pair was inserted into a free bucket
+    Vacant { index: usize, state: BucketState },
+    /// The value of an existing key was replaced
+    Value { index: usize, value: V },
+    /// A pair was removed
+    Entry { index: usize, key: K, value: V },
+}
+
+impl<'a, K, V, const N: usize, const LOAD: usize, const L: usize>
+    HashMapTransaction<'a, K, V, N, LOAD, L>
+where
+    K: Hash + PartialEq,
+{
+    /// Inserts a key-value pair, replacing the value if the key already exists
+    pub fn insert(&mut self, key: K, value: V) -> Result<(), HashMapError> {
+        if self.log.is_full() {
+            return Err(HashMapError::UndoLogFull);
+        }
+
+        let map = &mut *self.map;
+        let (index, found) = if map.is_full() {
+            let index = map.find_bucket_ro(&key).ok_or(HashMapError::Full)?;
+            (index, true)
+        } else {
+            map.find_bucket(&key)
+        };
+        let bucket = &mut map.buckets[index];
+
+        let undo = if found {
+            let old_value = unsafe { ptr::replace(bucket.value.as_mut_ptr(), value) };
+            Undo::Value {
+                index,
+                value: old_value,
+            }
+        } else {
+            bucket.key.write(key);
+            bucket.value.write(value);
+            let state = core::mem::replace(&mut map.states[index], BucketState::Occupied);
+            map.len += 1;
+            Undo::Vacant { index, state }
+        };
+
+        self.record(undo);
+        Ok(())
+    }
+
+    /// Removes a key-value pair, returning whether the key was present
+    pub fn remove(&mut self, key: &K) -> Result<bool, HashMapError> {
+        let Some(index) = self.map.find_bucket_ro(key) else {
+            return Ok(false);
+        };
+        if self.log.is_full() {
+            return Err(HashMapError::UndoLogFull);
+        }
+
+        let (key, value) = unsafe { self.map.take_bucket(index) };
+        self.record(Undo::Entry { index, key, value });
+        Ok(true)
+    }
+}
+
+impl<'a, K, V, const N: usize, const LOAD: usize, const L: usize>
+    HashMapTransaction<'a, K, V, N, LOAD, L>
+{
+    /// Keeps every change, dropping the values they displaced
+    pub fn commit(mut self) {
+        // disarm the guard first, a panicking destructor in the log mustn't roll back
+        let log = core::mem::take(&mut self.log);
+        core::mem::forget(self);
+        drop(log);
+    }
+
+    /// Restores every bucket changed since [`HashMap::begin`], same as dropping the guard
+    pub fn rollback(self) {}
+
+    /// Number of changes recorded so far
+    #[inline]
+    pub fn changes(&self) -> usize {
+        self.log.len()
+    }
+
+    fn record(&mut self, undo: Undo<K, V>) {
+        if self.log.push(undo).is_err() {
+            unreachable!("HashMapTransaction undo log checked for room");
+        }
+    }
+}
+
+impl<'a, K, V, const N: usize, const LOAD: usize, const L: usize> Deref
+    for HashMapTransaction<'a, K, V, N, LOAD, L>
+{
+    type Target = HashMap<K, V, N, LOAD>;
+
+    fn deref(&self) -> &Self::Target {
+        self.map
+    }
+}
+
+impl<'a, K, V, const N: usize, const LOAD: usize, const L: usize> Drop
+    for HashMapTransaction<'a, K, V, N, LOAD, L>
+{
+    fn drop(&mut self) {
+        // undo in reverse, so each bucket ends up as it was before its first change.
+        // The state and `len` are restored before anything is dropped, so a panicking
+        // destructor leaves the map consistent, with the remaining changes kept.
+        while let Some(undo) = self.log.pop() {
+            match undo {
+                Undo::Vacant { index, state } => {
+                    self.map.states[index] = state;
+                    self.map.len -= 1;
+                    let bucket = &mut self.map.buckets[index];
+                    let (key, value) = unsafe {
+                        (
+                            bucket.key.assume_init_read(),
+                            bucket.value.assume_init_read(),
+                        )
+                    };
+                    drop((key, value));
+                }
+                Undo::Value { index, value } => {
+                    let slot = unsafe { self.map.buckets[index].value.assume_init_mut() };
+                    drop(core::mem::replace(slot, value));
+                }
+                Undo::Entry { index, key, value } => {
+                    let bucket = &mut self.map.buckets[index];
+                    bucket.key.write(key);
+                    bucket.value.write(value);
+                    self.map.states[index] = BucketState::Occupied;
+                    self.map.len += 1;
+                }
+            }
+        }
+        debug_assert_eq!(self.map.len, self.len);
+    }
+}
+
+/// Per-bucket metadata, kept in its own array so it's always initialized
+/// and a probe only touches one byte per bucket
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+enum BucketState {
+    Empty,
+    Occupied,
+    Deleted,
+}
+
+impl BucketState {
+    #[inline]
+    fn is_occupied(self) -> bool {
+        matches!(self, BucketState::Occupied)
+    }
+}
+
+struct Bucket<K, V> {
+    key: MaybeUninit<K>,
+    value: MaybeUninit<V>,
+}
+
+impl<K, V> Bucket<K, V> {
+    const fn new() -> Self {
+        Self {
+            key: MaybeUninit::uninit(),
+            value: MaybeUninit::uninit(),
+        }
+    }
+}
+
+/// Number of entries `capacity` buckets hold at a load factor of `load` percent,
+/// rounded down without overflowing for large capacities
+#[inline]
+pub(crate) const fn load_limit(capacity: usize, load: usize) -> usize {
+    capacity / 100 * load + capacity % 100 * load / 100
+}
+
+/// Linear probe sequence over a table of `N` buckets, shared by [`HashMap`] and
+/// [`LruCache`](crate::LruCache) so both walk their tables the same way.
+///
+/// Starts at the home bucket of a hash and visits every bucket once, wrapping around
+/// the end of the table.
+pub(crate) struct Probe<const N: usize> {
+    index: usize,
+    remaining: usize,
+}
+
+impl<const N: usize> Probe<N> {
+    #[inline]
+    pub(crate) const fn new(hash: usize) -> Self {
+        Self {
+            index: hash & (N - 1),
+            remaining: N,
+        }
+    }
+
+    /// Same as [`Iterator::next`], but usable in a `const fn`
+    #[inline]
+    pub(crate) const fn next_index(&mut self) -> Option<usize> {
+        if self.remaining == 0 {
+            return None;
+        }
+
+        let index = self.index;
+        self.index = (index + 1) & (N - 1);
+        self.remaining -= 1;
+        Some(index)
+    }
+}
+
+impl<const N: usize> Iterator for Probe<N> {
+    type Item = usize;
+
+    #[inline]
+    fn next(&mut self) -> Option<usize> {
+        self.next_index()
+    }
+}
+
+/// Number of probe steps from the home bucket of `hash` to `index`
+#[inline]
+pub(crate) const fn probe_distance<const N: usize>(hash: usize, index: usize) -> usize {
+    index.wrapping_sub(hash) & (N - 1)
+}
+
+/// Hashes a key with [`Fnv1aHasher`], shared by every hash-based container in the crate
+#[inline]
+pub(crate) fn fnv1a_hash<K: Hash + ?Sized>(key: &K) -> u64 {
+    let mut hasher = Fnv1aHasher::new();
+    key.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// A dead-simple (and fast, of course) hash function based on FNV-1a
+pub(crate) struct Fnv1aHasher {
+    state: u64,
+}
+
+impl Fnv1aHasher {
+    pub(crate) const fn new() -> Self {
+        Self {
+            state: 0xcbf29ce484222325,
+        }
+    }
+
+    /// Same as [`Hasher::write`], but usable in const contexts
+    pub(crate) const fn write_bytes(&mut self, bytes: &[u8]) {
+        const FNV_PRIME: u64 = 0x100000001b3;
+        let mut i = 0;
+        while i < bytes.len() {
+            self.state ^= bytes[i] as u64;
+            self.state = self.state.wrapping_mul(FNV_PRIME);
+            i += 1;
+        }
+    }
+
+    pub(crate) const fn state(&self) -> u64 {
+        self.state
+    }
+}
+
+impl Hasher for Fnv1aHasher {
+    fn finish(&self) -> u64 {
+        self.state
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        self.write_bytes(bytes);
+    }
+}
+
+/// Probe statistics returned by [`HashMap::probe_stats`]
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct ProbeStats {
+    /// Furthest any entry sits from its home bucket
+    pub max_probe: usize,
+    /// Average distance of an entry from its home bucket
+    pub mean_probe: f32,
+    /// Deleted buckets that lookups still have to walk past
+    pub tombstones: usize,
+    /// Longest run of consecutive occupied buckets, wrapping around the end of the table
+    pub longest_cluster: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HashMapError {
+    Full,
+    NotFound,
+    UndoLogFull,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::format;
+    use std::rc::Rc;
+    use std::string::{String, ToString};
+    use std::vec::Vec;
+
+    #[test]
+    fn test_access() {
+        let mut map = HashMap::<u32, String, 8>::new();
 
         assert!(map.insert(42, "hello".to_string()).unwrap().is_none());
         assert_eq!(map.len(), 1);
@@ -424,4 +1421,668 @@ mod tests {
         map.insert(2, "two".to_string()).unwrap();
         assert_eq!(map.load_factor(), 0.25);
     }
+
+    // The tests below exercise every path that reads bucket metadata or drops
+    // bucket contents, so they're the ones worth running under Miri.
+
+    #[test]
+    fn test_insert_drop() {
+        let tracker = Rc::new(());
+        {
+            let mut map = HashMap::<u32, Rc<()>, 8>::new();
+            for i in 0..4 {
+                map.insert(i, tracker.clone()).unwrap();
+            }
+            assert_eq!(Rc::strong_count(&tracker), 5);
+
+            // replacing hands back the old value without leaking the new one
+            let old = map.insert(0, tracker.clone()).unwrap();
+            assert!(old.is_some());
+            drop(old);
+            assert_eq!(Rc::strong_count(&tracker), 5);
+        }
+        assert_eq!(Rc::strong_count(&tracker), 1);
+    }
+
+    #[test]
+    fn test_remove_drop() {
+        let tracker = Rc::new(());
+        let mut map = HashMap::<Rc<()>, Rc<()>, 8>::new();
+
+        map.insert(tracker.clone(), tracker.clone()).unwrap();
+        assert_eq!(Rc::strong_count(&tracker), 3);
+
+        let value = map.remove(&tracker).unwrap();
+        assert_eq!(Rc::strong_count(&tracker), 2);
+        drop(value);
+        assert_eq!(Rc::strong_count(&tracker), 1);
+
+        drop(map);
+        assert_eq!(Rc::strong_count(&tracker), 1);
+    }
+
+    #[test]
+    fn test_clear_drop() {
+        let tracker = Rc::new(());
+        let mut map = HashMap::<u32, Rc<()>, 8>::new();
+
+        for i in 0..5 {
+            map.insert(i, tracker.clone()).unwrap();
+        }
+        map.remove(&2);
+        assert_eq!(Rc::strong_count(&tracker), 5);
+
+        map.clear();
+        assert_eq!(Rc::strong_count(&tracker), 1);
+
+        map.insert(7, tracker.clone()).unwrap();
+        drop(map);
+        assert_eq!(Rc::strong_count(&tracker), 1);
+    }
+
+    #[test]
+    fn test_empty_drop() {
+        let map = HashMap::<String, String, 64>::new();
+        assert!(map.get(&"missing".to_string()).is_none());
+        assert_eq!(map.iter().count(), 0);
+    }
+
+    #[test]
+    fn test_tombstone_reuse() {
+        let mut map = HashMap::<u32, u32, 8>::new();
+
+        // churn enough keys through the table to leave every bucket deleted
+        for i in 0..64 {
+            map.insert(i, i).unwrap();
+            assert_eq!(map.remove(&i), Some(i));
+        }
+        assert!(map.is_empty());
+
+        for i in 0..6 {
+            map.insert(i, i * 10).unwrap();
+        }
+        for i in 0..6 {
+            assert_eq!(map.get(&i), Some(&(i * 10)));
+        }
+        assert_eq!(map.get(&100), None);
+        assert_eq!(map.iter().count(), 6);
+    }
+
+    #[test]
+    fn test_iter_len() {
+        let mut map = HashMap::<u32, u32, 16>::new();
+        for i in 0..6 {
+            map.insert(i, i).unwrap();
+        }
+
+        let mut iter = map.iter();
+        assert_eq!(iter.len(), 6);
+        iter.next();
+        iter.next();
+        assert_eq!(iter.len(), 4);
+        assert_eq!(iter.size_hint(), (4, Some(4)));
+        assert_eq!(iter.count(), 4);
+    }
+
+    #[test]
+    fn test_iter_mut() {
+        let mut map = HashMap::<u32, u32, 16>::new();
+        for i in 0..6 {
+            map.insert(i, i).unwrap();
+        }
+
+        for (key, value) in map.iter_mut() {
+            *value += key * 10;
+        }
+        for i in 0..6 {
+            assert_eq!(map.get(&i), Some(&(i * 11)));
+        }
+    }
+
+    #[test]
+    fn test_keys_values() {
+        let mut map = HashMap::<u32, u32, 16>::new();
+        for i in 0..6 {
+            map.insert(i, i * 2).unwrap();
+        }
+
+        let mut keys: Vec<_> = map.keys().copied().collect();
+        keys.sort();
+        assert_eq!(keys, [0, 1, 2, 3, 4, 5]);
+
+        for value in map.values_mut() {
+            *value += 1;
+        }
+        let mut values: Vec<_> = map.values().copied().collect();
+        values.sort();
+        assert_eq!(values, [1, 3, 5, 7, 9, 11]);
+        assert_eq!(map.values().len(), 6);
+    }
+
+    #[test]
+    fn test_into_iter() {
+        let tracker = Rc::new(());
+        let mut map = HashMap::<u32, Rc<()>, 16>::new();
+        for i in 0..6 {
+            map.insert(i, tracker.clone()).unwrap();
+        }
+
+        let mut iter = map.into_iter();
+        assert_eq!(iter.len(), 6);
+        let (_, first) = iter.next().unwrap();
+        assert_eq!(iter.len(), 5);
+        drop(first);
+        assert_eq!(Rc::strong_count(&tracker), 6);
+
+        drop(iter);
+        assert_eq!(Rc::strong_count(&tracker), 1);
+    }
+
+    #[test]
+    fn test_drain() {
+        let mut map = HashMap::<u32, String, 16>::new();
+        for i in 0..6 {
+            map.insert(i, format!("value{}", i)).unwrap();
+        }
+
+        let mut pairs: Vec<_> = map.drain().collect();
+        pairs.sort();
+        assert_eq!(pairs.len(), 6);
+        assert_eq!(pairs[3], (3, "value3".to_string()));
+        assert!(map.is_empty());
+
+        map.insert(1, "again".to_string()).unwrap();
+        assert_eq!(map.get(&1), Some(&"again".to_string()));
+    }
+
+    #[test]
+    fn test_drain_early_drop() {
+        let tracker = Rc::new(());
+        let mut map = HashMap::<u32, Rc<()>, 16>::new();
+        for i in 0..6 {
+            map.insert(i, tracker.clone()).unwrap();
+        }
+
+        let mut drain = map.drain();
+        assert_eq!(drain.len(), 6);
+        drain.next();
+        assert_eq!(drain.len(), 5);
+        drop(drain);
+
+        assert!(map.is_empty());
+        assert_eq!(Rc::strong_count(&tracker), 1);
+        assert_eq!(map.iter().count(), 0);
+    }
+
+    #[test]
+    fn test_drain_leak() {
+        let mut map = HashMap::<u32, u32, 16>::new();
+        for i in 0..6 {
+            map.insert(i, i).unwrap();
+        }
+
+        let mut drain = map.drain();
+        let (taken, _) = drain.next().unwrap();
+        core::mem::forget(drain);
+
+        assert_eq!(map.len(), 5);
+        assert_eq!(map.get(&taken), None);
+        assert_eq!(map.iter().count(), 5);
+    }
+
+    #[test]
+    fn test_drain_panic() {
+        use std::panic::{self, AssertUnwindSafe};
+
+        struct Bomb(bool);
+        impl Drop for Bomb {
+            fn drop(&mut self) {
+                if self.0 {
+                    panic!("boom");
+                }
+            }
+        }
+
+        let mut map = HashMap::<u32, Bomb, 16>::new();
+        for i in 0..6 {
+            map.insert(i, Bomb(i == 0)).unwrap();
+        }
+
+        let result = panic::catch_unwind(AssertUnwindSafe(|| drop(map.drain())));
+        assert!(result.is_err());
+
+        // the bomb is gone and whatever wasn't reached is still reachable
+        assert_eq!(map.iter().count(), map.len());
+        for (key, _) in map.iter() {
+            assert_ne!(*key, 0);
+        }
+    }
+
+    #[test]
+    fn test_retain() {
+        let mut map = HashMap::<u32, u32, 16>::new();
+        for i in 0..10 {
+            map.insert(i, i).unwrap();
+        }
+
+        map.retain(|key, value| {
+            *value *= 2;
+            key % 2 == 0
+        });
+        assert_eq!(map.len(), 5);
+        for i in 0..10 {
+            let expected = if i % 2 == 0 { Some(i * 2) } else { None };
+            assert_eq!(map.get(&i).copied(), expected);
+        }
+    }
+
+    #[test]
+    fn test_extract_if() {
+        let mut map = HashMap::<u32, u32, 16>::new();
+        for i in 0..10 {
+            map.insert(i, i).unwrap();
+        }
+
+        let mut extracted: Vec<_> = map.extract_if(|key, _| *key >= 7).collect();
+        extracted.sort();
+        assert_eq!(extracted, [(7, 7), (8, 8), (9, 9)]);
+        assert_eq!(map.len(), 7);
+        assert!(!map.contains_key(&8));
+
+        // dropping early leaves the rest in place
+        assert!(map.extract_if(|_, _| true).next().is_some());
+        assert_eq!(map.len(), 6);
+    }
+
+    #[test]
+    fn test_max_len() {
+        assert_eq!(HashMap::<u32, u32, 8>::new().max_len(), 6);
+        assert_eq!(HashMap::<u32, u32, 1024>::new().max_len(), 768);
+        assert_eq!(HashMap::<u32, u32, 1024, 90>::new().max_len(), 921);
+        assert_eq!(HashMap::<u32, u32, 1024, 50>::new().max_len(), 512);
+        assert_eq!(HashMap::<u32, u32, 2, 99>::new().max_len(), 1);
+    }
+
+    #[test]
+    fn test_custom_load() {
+        let mut dense = HashMap::<u32, u32, 16, 90>::new();
+        for i in 0..14 {
+            dense.insert(i, i).unwrap();
+        }
+        assert!(dense.is_full());
+        assert_eq!(dense.insert(14, 14), Err(HashMapError::Full));
+        for i in 0..14 {
+            assert_eq!(dense.get(&i), Some(&i));
+        }
+
+        let mut sparse = HashMap::<u32, u32, 16, 50>::new();
+        for i in 0..8 {
+            sparse.insert(i, i).unwrap();
+        }
+        assert!(sparse.is_full());
+        assert_eq!(sparse.insert(8, 8), Err(HashMapError::Full));
+    }
+
+    #[test]
+    fn test_clone() {
+        let mut map = HashMap::<u32, String, 16>::new();
+        for i in 0..8 {
+            map.insert(i, format!("value{}", i)).unwrap();
+        }
+        map.remove(&3);
+
+        let mut snapshot = map.clone();
+        assert_eq!(snapshot, map);
+
+        snapshot.insert(3, "back".to_string()).unwrap();
+        assert_ne!(snapshot, map);
+        assert_eq!(map.get(&3), None);
+        assert_eq!(snapshot[&7], "value7");
+    }
+
+    #[test]
+    fn test_debug() {
+        let mut map = HashMap::<u32, u32, 8>::new();
+        assert_eq!(format!("{:?}", map), "{}");
+
+        map.insert(1, 10).unwrap();
+        assert_eq!(format!("{:?}", map), "{1: 10}");
+    }
+
+    #[test]
+    fn test_eq_ignores_layout() {
+        let a: HashMap<u32, u32, 16> = (0..8).map(|i| (i, i)).collect();
+        let b: HashMap<u32, u32, 16> = (0..8).rev().map(|i| (i, i)).collect();
+        assert_eq!(a, b);
+
+        let c: HashMap<u32, u32, 16> = (0..7).map(|i| (i, i)).collect();
+        assert_ne!(a, c);
+    }
+
+    #[test]
+    #[should_panic(expected = "Key not found")]
+    fn test_index_missing() {
+        let map = HashMap::<u32, u32, 8>::new();
+        let _ = map[&1];
+    }
+
+    #[test]
+    fn test_try_from_iter() {
+        let map = HashMap::<u32, u32, 8>::try_from_iter((0..6).map(|i| (i, i))).unwrap();
+        assert_eq!(map.len(), 6);
+
+        let overflow = HashMap::<u32, u32, 8>::try_from_iter((0..7).map(|i| (i, i)));
+        assert_eq!(overflow.err(), Some(HashMapError::Full));
+
+        let mut map = HashMap::<u32, u32, 8>::new();
+        assert_eq!(map.try_extend([(1, 1), (2, 2)]), Ok(()));
+        assert_eq!(
+            map.try_extend((10..20).map(|i| (i, i))),
+            Err(HashMapError::Full)
+        );
+        assert!(map.is_full());
+    }
+
+    #[test]
+    #[should_panic(expected = "HashMap capacity exceeded")]
+    fn test_extend_overflow() {
+        let mut map = HashMap::<u32, u32, 8>::new();
+        map.extend((0..7).map(|i| (i, i)));
+    }
+
+    #[test]
+    fn test_ref_into_iter() {
+        let mut map: HashMap<u32, u32, 8> = (0..4).map(|i| (i, i)).collect();
+
+        for (_, value) in &mut map {
+            *value += 1;
+        }
+
+        let mut total = 0;
+        for (_, value) in &map {
+            total += value;
+        }
+        assert_eq!(total, 10);
+    }
+
+    #[test]
+    fn test_get_many_mut() {
+        let mut map = HashMap::<&str, i32, 16>::new();
+        map.insert("alice", 100).unwrap();
+        map.insert("bob", 50).unwrap();
+
+        if let Some([from, to]) = map.get_many_mut([&"alice", &"bob"]) {
+            *from -= 30;
+            *to += 30;
+        }
+        assert_eq!(map.get(&"alice"), Some(&70));
+        assert_eq!(map.get(&"bob"), Some(&80));
+
+        assert!(map.get_many_mut([&"alice", &"alice"]).is_none());
+        assert!(map.get_many_mut([&"alice", &"carol"]).is_none());
+        assert_eq!(map.get_many_mut::<0>([]), Some([]));
+
+        let [bob] = (unsafe { map.get_many_unchecked_mut([&"bob"]) }).unwrap();
+        *bob = 0;
+        assert_eq!(map[&"bob"], 0);
+    }
+
+    #[test]
+    fn test_probe_stats() {
+        let mut map = HashMap::<u32, u32, 16>::new();
+        let mut histogram = [usize::MAX; 4];
+        assert_eq!(map.probe_stats(&mut histogram), ProbeStats::default());
+        assert_eq!(histogram, [0; 4]);
+
+        for i in 0..12 {
+            map.insert(i, i).unwrap();
+        }
+        map.remove(&3);
+        map.remove(&7);
+
+        let stats = map.probe_stats(&mut histogram);
+        assert_eq!(stats.tombstones, 2);
+        assert_eq!(histogram.iter().sum::<usize>(), 10);
+
+        // check against the distances straight from the hash
+        let mut max = 0;
+        let mut total = 0;
+        for (index, state) in map.states.iter().enumerate() {
+            if state.is_occupied() {
+                let key = unsafe { map.buckets[index].key.assume_init_ref() };
+                let distance = index.wrapping_sub(map.hash_key(key)) & 15;
+                max = max.max(distance);
+                total += distance;
+            }
+        }
+        assert_eq!(stats.max_probe, max);
+        assert_eq!(stats.mean_probe, total as f32 / 10.0);
+        assert!(stats.longest_cluster >= 1 && stats.longest_cluster <= 10);
+    }
+
+    #[test]
+    fn test_probe_stats_wrapping_cluster() {
+        let mut map = HashMap::<u32, u32, 8>::new();
+        for i in 0..6 {
+            map.insert(i, i).unwrap();
+        }
+        let empty: std::vec::Vec<_> = (0..8).filter(|&i| !map.states[i].is_occupied()).collect();
+        assert_eq!(empty.len(), 2);
+
+        // the two empty buckets split the table into runs, one of them possibly wrapping
+        let (a, b) = (empty[0], empty[1]);
+        let inner = b - a - 1;
+        let outer = 8 - b - 1 + a;
+        let stats = map.probe_stats(&mut []);
+        assert_eq!(stats.longest_cluster, inner.max(outer));
+    }
+
+    #[test]
+    fn test_try_insert() {
+        let mut map = HashMap::<u32, String, 4>::new();
+        for i in 0..3 {
+            assert_eq!(map.try_insert(i, i.to_string()), Ok(None));
+        }
+        assert!(map.is_full());
+
+        match map.try_insert(3, "three".to_string()) {
+            Err((key, value)) => {
+                assert_eq!(key, 3);
+                assert_eq!(value, "three");
+            }
+            Ok(_) => panic!("Should have failed"),
+        }
+
+        // replacing doesn't need a free bucket
+        assert_eq!(
+            map.try_insert(1, "uno".to_string()),
+            Ok(Some("1".to_string()))
+        );
+        assert_eq!(map.len(), 3);
+    }
+
+    #[test]
+    fn test_insert_full() {
+        let mut map = HashMap::<u32, u32, 4>::new();
+        for i in 0..3 {
+            map.insert(i, i).unwrap();
+        }
+        assert!(map.is_full());
+
+        // a full map still takes a new value for a key it holds
+        assert_eq!(map.insert(2, 20), Ok(Some(2)));
+        assert_eq!(map.insert(3, 30), Err(HashMapError::Full));
+        assert_eq!(map.get(&2), Some(&20));
+        assert_eq!(map.len(), 3);
+    }
+
+    #[test]
+    fn test_hashmap_macro() {
+        let map: HashMap<&str, u32, 4> = hashmap! {
+            "one" => 1,
+            "two" => 2,
+            "one" => 11,
+        };
+        assert_eq!(map.len(), 2);
+        assert_eq!(map[&"one"], 11);
+
+        let empty: HashMap<u32, u32, 4> = hashmap! {};
+        assert!(empty.is_empty());
+        assert_eq!(HashMap::<u8, u8, 4>::from([(1, 2)]).get(&1), Some(&2));
+    }
+
+    #[test]
+    fn test_hashmap_macro_static() {
+        static PORTS: HashMap<u16, &str, 8> = hashmap! {
+            80 => "http",
+            443 => "https",
+            22 => "ssh",
+            80 => "www",
+            8080 => "alt",
+        };
+        assert_eq!(PORTS.len(), 4);
+        assert_eq!(PORTS.get(&80), Some(&"www"));
+        assert_eq!(PORTS.get(&443), Some(&"https"));
+        assert_eq!(PORTS.get(&22), Some(&"ssh"));
+        assert_eq!(PORTS.get(&8080), Some(&"alt"));
+        assert_eq!(PORTS.get(&21), None);
+
+        let map: HashMap<&str, String, 4> =
+            HashMap::from_array([("a", "first".to_string()), ("a", "second".to_string())]);
+        assert_eq!(map.len(), 1);
+        assert_eq!(map[&"a"], "second");
+    }
+
+    #[test]
+    fn test_transaction_rollback() {
+        let mut map = HashMap::<u32, String, 16>::new();
+        for i in 0..6 {
+            map.insert(i, i.to_string()).unwrap();
+        }
+        map.remove(&5);
+        let states_before = map.states;
+
+        {
+            let mut tx = map.begin::<16>();
+            tx.insert(0, "zero".to_string()).unwrap();
+            tx.insert(0, "nil".to_string()).unwrap();
+            assert_eq!(tx.remove(&1), Ok(true));
+            assert_eq!(tx.remove(&1), Ok(false));
+            tx.insert(1, "one again".to_string()).unwrap();
+            tx.insert(7, "seven".to_string()).unwrap();
+            tx.insert(8, "eight".to_string()).unwrap();
+            assert_eq!(tx.remove(&8), Ok(true));
+            assert_eq!(tx.get(&0), Some(&"nil".to_string()));
+            assert_eq!(tx.len(), 6);
+            assert_eq!(tx.changes(), 7);
+            tx.rollback();
+        }
+
+        assert_eq!(map.len(), 5);
+        assert!(map.states == states_before);
+        for i in 0..5 {
+            assert_eq!(map.get(&i), Some(&i.to_string()));
+        }
+        assert_eq!(map.get(&7), None);
+    }
+
+    #[test]
+    fn test_transaction_commit() {
+        let mut map = HashMap::<u32, u32, 16>::new();
+        map.insert(1, 1).unwrap();
+
+        let mut tx = map.begin::<4>();
+        tx.insert(1, 10).unwrap();
+        tx.insert(2, 20).unwrap();
+        tx.remove(&1).unwrap();
+        tx.commit();
+
+        assert_eq!(map.len(), 1);
+        assert_eq!(map.get(&1), None);
+        assert_eq!(map.get(&2), Some(&20));
+    }
+
+    #[test]
+    fn test_transaction_log_full() {
+        let mut map = HashMap::<u32, u32, 16>::new();
+        map.insert(1, 1).unwrap();
+
+        let mut tx = map.begin::<2>();
+        tx.insert(2, 2).unwrap();
+        tx.insert(3, 3).unwrap();
+        assert_eq!(tx.insert(4, 4), Err(HashMapError::UndoLogFull));
+        assert_eq!(tx.remove(&1), Err(HashMapError::UndoLogFull));
+        // nothing to record, so nothing to refuse
+        assert_eq!(tx.remove(&9), Ok(false));
+        assert_eq!(tx.get(&4), None);
+        assert_eq!(tx.get(&1), Some(&1));
+        drop(tx);
+
+        assert_eq!(map.len(), 1);
+        assert_eq!(map.get(&2), None);
+    }
+
+    #[test]
+    fn test_transaction_panic() {
+        use std::panic::{self, AssertUnwindSafe};
+
+        struct Bomb(bool);
+        impl Drop for Bomb {
+            fn drop(&mut self) {
+                if self.0 {
+                    panic!("boom");
+                }
+            }
+        }
+
+        // a displaced value panicking on commit keeps the committed changes
+        let mut map = HashMap::<u32, Bomb, 16>::new();
+        map.insert(1, Bomb(true)).unwrap();
+        let mut tx = map.begin::<4>();
+        tx.insert(1, Bomb(false)).unwrap();
+        tx.insert(2, Bomb(false)).unwrap();
+        let result = panic::catch_unwind(AssertUnwindSafe(|| tx.commit()));
+        assert!(result.is_err());
+        assert_eq!(map.len(), 2);
+        assert!(!map.get(&1).unwrap().0);
+        assert!(map.contains_key(&2));
+
+        // an inserted value panicking on rollback leaves `len` matching the buckets
+        let mut tx = map.begin::<4>();
+        tx.remove(&2).unwrap();
+        tx.insert(3, Bomb(true)).unwrap();
+        tx.insert(4, Bomb(false)).unwrap();
+        let result = panic::catch_unwind(AssertUnwindSafe(|| drop(tx)));
+        assert!(result.is_err());
+        assert_eq!(map.iter().count(), map.len());
+        assert!(!map.contains_key(&3));
+        assert!(!map.contains_key(&4));
+    }
+
+    #[test]
+    fn test_transaction_drop() {
+        let tracker = Rc::new(());
+        let mut map = HashMap::<u32, Rc<()>, 8>::new();
+        map.insert(1, tracker.clone()).unwrap();
+        map.insert(2, tracker.clone()).unwrap();
+
+        {
+            let mut tx = map.begin::<8>();
+            tx.insert(1, tracker.clone()).unwrap();
+            tx.insert(3, tracker.clone()).unwrap();
+            tx.remove(&2).unwrap();
+            // displaced values are held by the log until the transaction ends
+            assert_eq!(Rc::strong_count(&tracker), 5);
+        }
+        assert_eq!(Rc::strong_count(&tracker), 3);
+
+        let mut tx = map.begin::<8>();
+        tx.insert(1, tracker.clone()).unwrap();
+        tx.remove(&2).unwrap();
+        tx.commit();
+        assert_eq!(Rc::strong_count(&tracker), 2);
+
+        drop(map);
+        assert_eq!(Rc::strong_count(&tracker), 1);
+    }
 }
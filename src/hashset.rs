@@ -0,0 +1,557 @@
+use core::hash::Hash;
+use core::iter::{Chain, FusedIterator};
+
+use crate::hashmap::{DEFAULT_LOAD, HashMap, HashMapIntoIter, HashMapKeys};
+
+/// Fixed capacity hash set, backed by the open addressing [`HashMap`].
+///
+/// Capacity must be a power of 2, and `LOAD` is the maximum load factor, as for
+/// [`HashMap`].
+pub struct HashSet<T, const N: usize, const LOAD: usize = DEFAULT_LOAD> {
+    map: HashMap<T, (), N, LOAD>,
+}
+
+impl<T, const N: usize, const LOAD: usize> HashSet<T, N, LOAD>
+where
+    T: Hash + PartialEq,
+{
+    /// Panics if `N` is not a power of 2 (or is 0)
+    pub const fn new() -> Self {
+        Self {
+            map: HashMap::new(),
+        }
+    }
+
+    /// Builds a set from an iterator, returning an error if it doesn't fit
+    pub fn try_from_iter<I>(iter: I) -> Result<Self, HashSetError>
+    where
+        I: IntoIterator<Item = T>,
+    {
+        let mut set = Self::new();
+        set.try_extend(iter)?;
+        Ok(set)
+    }
+
+    #[inline]
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Maximum number of values the set accepts, see [`HashMap::max_len`]
+    #[inline]
+    pub const fn max_len(&self) -> usize {
+        self.map.max_len()
+    }
+
+    #[inline]
+    pub const fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    #[inline]
+    pub const fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    #[inline]
+    pub const fn is_full(&self) -> bool {
+        self.map.is_full()
+    }
+
+    /// Adds a value to the set, returning `false` if an equal value was already present.
+    ///
+    /// The stored value is kept when an equal one is inserted.
+    pub fn insert(&mut self, value: T) -> Result<bool, HashSetError> {
+        self.map
+            .try_insert(value, ())
+            .map(|old| old.is_none())
+            .map_err(|_| HashSetError::Full)
+    }
+
+    /// Adds a value to the set, replacing and returning an equal value if one was present
+    pub fn replace(&mut self, value: T) -> Result<Option<T>, HashSetError> {
+        match self.map.replace_key(value) {
+            Ok(old) => Ok(Some(old)),
+            Err(value) => self
+                .map
+                .insert(value, ())
+                .map(|_| None)
+                .map_err(|_| HashSetError::Full),
+        }
+    }
+
+    pub fn contains(&self, value: &T) -> bool {
+        self.map.contains_key(value)
+    }
+
+    /// Returns a reference to the stored value equal to `value`
+    pub fn get(&self, value: &T) -> Option<&T> {
+        self.map.get_key_value(value).map(|(stored, _)| stored)
+    }
+
+    /// Removes a value from the set, returning whether it was present
+    pub fn remove(&mut self, value: &T) -> bool {
+        self.map.remove(value).is_some()
+    }
+
+    /// Removes and returns the stored value equal to `value`
+    pub fn take(&mut self, value: &T) -> Option<T> {
+        self.map.remove_entry(value).map(|(stored, _)| stored)
+    }
+
+    pub fn clear(&mut self) {
+        self.map.clear();
+    }
+
+    /// Extends the set with the contents of an iterator, stopping at the first value that doesn't fit
+    pub fn try_extend<I>(&mut self, iter: I) -> Result<(), HashSetError>
+    where
+        I: IntoIterator<Item = T>,
+    {
+        for value in iter {
+            self.insert(value)?;
+        }
+        Ok(())
+    }
+
+    /// Values in `self` or `other`, without duplicates
+    pub fn union<'a, const M: usize>(
+        &'a self,
+        other: &'a HashSet<T, M, LOAD>,
+    ) -> Union<'a, T, N, M, LOAD> {
+        Union {
+            inner: self.iter().chain(other.difference(self)),
+        }
+    }
+
+    /// Values in both `self` and `other`
+    pub fn intersection<'a, const M: usize>(
+        &'a self,
+        other: &'a HashSet<T, M, LOAD>,
+    ) -> Intersection<'a, T, N, M, LOAD> {
+        Intersection {
+            iter: self.iter(),
+            other,
+        }
+    }
+
+    /// Values in `self` but not in `other`
+    pub fn difference<'a, const M: usize>(
+        &'a self,
+        other: &'a HashSet<T, M, LOAD>,
+    ) -> Difference<'a, T, N, M, LOAD> {
+        Difference {
+            iter: self.iter(),
+            other,
+        }
+    }
+
+    /// Values in exactly one of `self` and `other`
+    pub fn symmetric_difference<'a, const M: usize>(
+        &'a self,
+        other: &'a HashSet<T, M, LOAD>,
+    ) -> SymmetricDifference<'a, T, N, M, LOAD> {
+        SymmetricDifference {
+            inner: self.difference(other).chain(other.difference(self)),
+        }
+    }
+
+    pub fn is_disjoint<const M: usize>(&self, other: &HashSet<T, M, LOAD>) -> bool {
+        self.iter().all(|value| !other.contains(value))
+    }
+
+    pub fn is_subset<const M: usize>(&self, other: &HashSet<T, M, LOAD>) -> bool {
+        self.len() <= other.len() && self.iter().all(|value| other.contains(value))
+    }
+
+    pub fn is_superset<const M: usize>(&self, other: &HashSet<T, M, LOAD>) -> bool {
+        other.is_subset(self)
+    }
+}
+
+impl<T, const N: usize, const LOAD: usize> HashSet<T, N, LOAD> {
+    pub fn iter(&self) -> HashSetIter<'_, T, N> {
+        HashSetIter {
+            inner: self.map.keys(),
+        }
+    }
+}
+
+impl<T, const N: usize, const LOAD: usize> Default for HashSet<T, N, LOAD>
+where
+    T: Hash + PartialEq,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Panics if the iterator yields more values than the set can hold,
+/// use [`HashSet::try_from_iter`] to handle that case.
+impl<T, const N: usize, const LOAD: usize> FromIterator<T> for HashSet<T, N, LOAD>
+where
+    T: Hash + PartialEq,
+{
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        Self::try_from_iter(iter).expect("HashSet capacity exceeded")
+    }
+}
+
+/// Panics if the set runs out of room, use [`HashSet::try_extend`] to handle that case.
+impl<T, const N: usize, const LOAD: usize> Extend<T> for HashSet<T, N, LOAD>
+where
+    T: Hash + PartialEq,
+{
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        self.try_extend(iter).expect("HashSet capacity exceeded");
+    }
+}
+
+impl<T, const N: usize, const LOAD: usize> IntoIterator for HashSet<T, N, LOAD> {
+    type Item = T;
+    type IntoIter = HashSetIntoIter<T, N, LOAD>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        HashSetIntoIter {
+            inner: self.map.into_iter(),
+        }
+    }
+}
+
+impl<'a, T, const N: usize, const LOAD: usize> IntoIterator for &'a HashSet<T, N, LOAD> {
+    type Item = &'a T;
+    type IntoIter = HashSetIter<'a, T, N>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+pub struct HashSetIter<'a, T, const N: usize> {
+    inner: HashMapKeys<'a, T, (), N>,
+}
+
+impl<'a, T, const N: usize> Iterator for HashSetIter<'a, T, N> {
+    type Item = &'a T;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next()
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<'a, T, const N: usize> ExactSizeIterator for HashSetIter<'a, T, N> {
+    fn len(&self) -> usize {
+        self.inner.len()
+    }
+}
+
+impl<'a, T, const N: usize> FusedIterator for HashSetIter<'a, T, N> {}
+
+pub struct HashSetIntoIter<T, const N: usize, const LOAD: usize = DEFAULT_LOAD> {
+    inner: HashMapIntoIter<T, (), N, LOAD>,
+}
+
+impl<T, const N: usize, const LOAD: usize> Iterator for HashSetIntoIter<T, N, LOAD> {
+    type Item = T;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(value, _)| value)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<T, const N: usize, const LOAD: usize> ExactSizeIterator for HashSetIntoIter<T, N, LOAD> {
+    fn len(&self) -> usize {
+        self.inner.len()
+    }
+}
+
+impl<T, const N: usize, const LOAD: usize> FusedIterator for HashSetIntoIter<T, N, LOAD> {}
+
+/// Lazy union of two sets, see [`HashSet::union`]
+pub struct Union<'a, T, const N: usize, const M: usize, const LOAD: usize = DEFAULT_LOAD> {
+    inner: Chain<HashSetIter<'a, T, N>, Difference<'a, T, M, N, LOAD>>,
+}
+
+impl<'a, T, const N: usize, const M: usize, const LOAD: usize> Iterator for Union<'a, T, N, M, LOAD>
+where
+    T: Hash + PartialEq,
+{
+    type Item = &'a T;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next()
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<'a, T, const N: usize, const M: usize, const LOAD: usize> FusedIterator
+    for Union<'a, T, N, M, LOAD>
+where
+    T: Hash + PartialEq,
+{
+}
+
+/// Lazy intersection of two sets, see [`HashSet::intersection`]
+pub struct Intersection<'a, T, const N: usize, const M: usize, const LOAD: usize = DEFAULT_LOAD> {
+    iter: HashSetIter<'a, T, N>,
+    other: &'a HashSet<T, M, LOAD>,
+}
+
+impl<'a, T, const N: usize, const M: usize, const LOAD: usize> Iterator
+    for Intersection<'a, T, N, M, LOAD>
+where
+    T: Hash + PartialEq,
+{
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let other = self.other;
+        self.iter.by_ref().find(|value| other.contains(value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, self.iter.size_hint().1)
+    }
+}
+
+impl<'a, T, const N: usize, const M: usize, const LOAD: usize> FusedIterator
+    for Intersection<'a, T, N, M, LOAD>
+where
+    T: Hash + PartialEq,
+{
+}
+
+/// Lazy difference of two sets, see [`HashSet::difference`]
+pub struct Difference<'a, T, const N: usize, const M: usize, const LOAD: usize = DEFAULT_LOAD> {
+    iter: HashSetIter<'a, T, N>,
+    other: &'a HashSet<T, M, LOAD>,
+}
+
+impl<'a, T, const N: usize, const M: usize, const LOAD: usize> Iterator
+    for Difference<'a, T, N, M, LOAD>
+where
+    T: Hash + PartialEq,
+{
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let other = self.other;
+        self.iter.by_ref().find(|value| !other.contains(value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, self.iter.size_hint().1)
+    }
+}
+
+impl<'a, T, const N: usize, const M: usize, const LOAD: usize> FusedIterator
+    for Difference<'a, T, N, M, LOAD>
+where
+    T: Hash + PartialEq,
+{
+}
+
+/// Lazy symmetric difference of two sets, see [`HashSet::symmetric_difference`]
+pub struct SymmetricDifference<
+    'a,
+    T,
+    const N: usize,
+    const M: usize,
+    const LOAD: usize = DEFAULT_LOAD,
+> {
+    inner: Chain<Difference<'a, T, N, M, LOAD>, Difference<'a, T, M, N, LOAD>>,
+}
+
+impl<'a, T, const N: usize, const M: usize, const LOAD: usize> Iterator
+    for SymmetricDifference<'a, T, N, M, LOAD>
+where
+    T: Hash + PartialEq,
+{
+    type Item = &'a T;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next()
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<'a, T, const N: usize, const M: usize, const LOAD: usize> FusedIterator
+    for SymmetricDifference<'a, T, N, M, LOAD>
+where
+    T: Hash + PartialEq,
+{
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HashSetError {
+    Full,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::string::{String, ToString};
+    use std::vec::Vec;
+
+    fn sorted<'a>(iter: impl Iterator<Item = &'a u32>) -> Vec<u32> {
+        let mut values: Vec<_> = iter.copied().collect();
+        values.sort();
+        values
+    }
+
+    #[test]
+    fn test_insert_contains() {
+        let mut set = HashSet::<u32, 8>::new();
+
+        assert_eq!(set.insert(42), Ok(true));
+        assert_eq!(set.insert(42), Ok(false));
+        assert_eq!(set.len(), 1);
+        assert!(set.contains(&42));
+        assert!(!set.contains(&7));
+    }
+
+    #[test]
+    fn test_remove_take() {
+        let mut set = HashSet::<String, 8>::new();
+        set.insert("a".to_string()).unwrap();
+        set.insert("b".to_string()).unwrap();
+
+        assert!(set.remove(&"a".to_string()));
+        assert!(!set.remove(&"a".to_string()));
+        assert_eq!(set.take(&"b".to_string()), Some("b".to_string()));
+        assert_eq!(set.take(&"b".to_string()), None);
+        assert!(set.is_empty());
+    }
+
+    #[test]
+    fn test_replace() {
+        // equality and hashing only look at the id, so the tag tells copies apart
+        #[derive(Debug)]
+        struct Tagged(u32, &'static str);
+        impl PartialEq for Tagged {
+            fn eq(&self, other: &Self) -> bool {
+                self.0 == other.0
+            }
+        }
+        impl core::hash::Hash for Tagged {
+            fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
+                self.0.hash(state);
+            }
+        }
+
+        let mut set = HashSet::<Tagged, 8>::new();
+        assert!(set.replace(Tagged(1, "old")).unwrap().is_none());
+
+        let old = set.replace(Tagged(1, "new")).unwrap().unwrap();
+        assert_eq!(old.1, "old");
+        assert_eq!(set.get(&Tagged(1, "")).unwrap().1, "new");
+        assert_eq!(set.len(), 1);
+    }
+
+    #[test]
+    fn test_full() {
+        let mut set = HashSet::<u32, 8>::new();
+        for i in 0..6 {
+            set.insert(i).unwrap();
+        }
+
+        assert!(set.is_full());
+        assert_eq!(set.insert(6), Err(HashSetError::Full));
+        // values that are already present don't need room
+        assert_eq!(set.insert(3), Ok(false));
+        assert_eq!(set.replace(3), Ok(Some(3)));
+    }
+
+    #[test]
+    fn test_set_operations() {
+        let a: HashSet<u32, 16> = (0..6).collect();
+        let b: HashSet<u32, 8> = (4..9).collect();
+
+        assert_eq!(sorted(a.union(&b)), [0, 1, 2, 3, 4, 5, 6, 7, 8]);
+        assert_eq!(sorted(a.intersection(&b)), [4, 5]);
+        assert_eq!(sorted(a.difference(&b)), [0, 1, 2, 3]);
+        assert_eq!(sorted(b.difference(&a)), [6, 7, 8]);
+        assert_eq!(sorted(a.symmetric_difference(&b)), [0, 1, 2, 3, 6, 7, 8]);
+    }
+
+    #[test]
+    fn test_subset_superset() {
+        let small: HashSet<u32, 8> = (2..4).collect();
+        let large: HashSet<u32, 16> = (0..6).collect();
+        let other: HashSet<u32, 8> = (10..12).collect();
+
+        assert!(small.is_subset(&large));
+        assert!(!large.is_subset(&small));
+        assert!(large.is_superset(&small));
+        assert!(small.is_disjoint(&other));
+        assert!(!small.is_disjoint(&large));
+    }
+
+    #[test]
+    fn test_from_iter_errors() {
+        assert!(HashSet::<u32, 8>::try_from_iter(0..6).is_ok());
+        assert_eq!(
+            HashSet::<u32, 8>::try_from_iter(0..7).err(),
+            Some(HashSetError::Full)
+        );
+
+        let mut set = HashSet::<u32, 8>::new();
+        assert!(set.try_extend([1, 2, 2, 3]).is_ok());
+        assert_eq!(set.len(), 3);
+        assert_eq!(set.try_extend(10..20), Err(HashSetError::Full));
+        assert!(set.is_full());
+    }
+
+    #[test]
+    #[should_panic(expected = "HashSet capacity exceeded")]
+    fn test_extend_overflow() {
+        let mut set = HashSet::<u32, 8>::new();
+        set.extend(0..7);
+    }
+
+    #[test]
+    fn test_into_iter() {
+        let set: HashSet<u32, 8> = [3, 1, 2].into_iter().collect();
+        assert_eq!(sorted(set.iter()), [1, 2, 3]);
+
+        let mut owned: Vec<_> = set.into_iter().collect();
+        owned.sort();
+        assert_eq!(owned, [1, 2, 3]);
+    }
+
+    #[test]
+    fn test_load_factor() {
+        let mut set = HashSet::<u32, 8, 50>::new();
+        assert_eq!(set.max_len(), 4);
+        for value in 0..4 {
+            assert_eq!(set.insert(value), Ok(true));
+        }
+        assert!(set.is_full());
+        assert_eq!(set.insert(4), Err(HashSetError::Full));
+
+        let other: HashSet<u32, 16, 50> = (2..6).filter(|value| *value != 4).collect();
+        assert_eq!(sorted(set.intersection(&other)), [2, 3]);
+    }
+}
@@ -0,0 +1,858 @@
+use core::cmp::Ordering;
+use core::hash::Hash;
+use core::iter::FusedIterator;
+use core::ops::{Index, IndexMut};
+use core::slice;
+
+use crate::fixedvec::{FixedVec, FixedVecIntoIter};
+use crate::hashmap::{DEFAULT_LOAD, fnv1a_hash, load_limit};
+
+const EMPTY: usize = usize::MAX;
+
+/// Hash map that remembers insertion order.
+///
+/// Entries are stored densely in a [`FixedVec`], with an open addressing index
+/// of positions on top. Like [`HashMap`](crate::HashMap), capacity must be a power
+/// of 2, and `LOAD` is the maximum load factor as a percentage of `N` (75% by
+/// default), which must leave at least one bucket empty.
+pub struct IndexMap<K, V, const N: usize, const LOAD: usize = DEFAULT_LOAD> {
+    entries: FixedVec<Entry<K, V>, N>,
+    indices: [usize; N],
+}
+
+struct Entry<K, V> {
+    hash: usize,
+    key: K,
+    value: V,
+}
+
+impl<K, V, const N: usize, const LOAD: usize> IndexMap<K, V, N, LOAD>
+where
+    K: Hash + PartialEq,
+{
+    const LOAD_CHECK: () = {
+        assert!(LOAD > 0, "IndexMap load factor must be greater than 0");
+        assert!(LOAD < 100, "IndexMap load factor must leave a bucket empty");
+    };
+
+    /// Panics if `N` is not a power of 2 (or is 0)
+    ///
+    /// Fails to compile if `LOAD` isn't between 1 and 99:
+    ///
+    /// ```compile_fail
+    /// use sakurai::IndexMap;
+    ///
+    /// let map = IndexMap::<u8, u8, 8, 100>::new();
+    /// ```
+    pub const fn new() -> Self {
+        assert!(N > 0, "IndexMap size must be greater than 0");
+        assert!(N.is_power_of_two(), "IndexMap size must be a power of 2");
+        let () = Self::LOAD_CHECK;
+
+        Self {
+            entries: FixedVec::new(),
+            indices: [EMPTY; N],
+        }
+    }
+
+    /// Inserts a key-value pair at the end of the map, returning the old value if the key
+    /// already exists. Replacing a value keeps the entry where it was.
+    pub fn insert(&mut self, key: K, value: V) -> Result<Option<V>, IndexMapError> {
+        let hash = fnv1a_hash(&key) as usize;
+
+        match self.find(hash, &key) {
+            Ok((_, pos)) => {
+                let entry = &mut self.entries[pos];
+                Ok(Some(core::mem::replace(&mut entry.value, value)))
+            }
+            Err(slot) => {
+                if self.is_full() {
+                    return Err(IndexMapError::Full);
+                }
+
+                self.indices[slot] = self.entries.len();
+                let pushed = self.entries.try_push(Entry { hash, key, value });
+                debug_assert!(pushed.is_ok());
+                Ok(None)
+            }
+        }
+    }
+
+    /// Get a reference to a value for a given key
+    pub fn get(&self, key: &K) -> Option<&V> {
+        self.get_index_of(key).map(|pos| &self.entries[pos].value)
+    }
+
+    /// Get a mut reference to a value for a given key
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        self.get_index_of(key)
+            .map(|pos| &mut self.entries[pos].value)
+    }
+
+    /// Get references to the stored key and its value for a given key
+    pub fn get_key_value(&self, key: &K) -> Option<(&K, &V)> {
+        self.get_index_of(key).map(|pos| {
+            let entry = &self.entries[pos];
+            (&entry.key, &entry.value)
+        })
+    }
+
+    /// Returns the position of a key in insertion order
+    pub fn get_index_of(&self, key: &K) -> Option<usize> {
+        let hash = fnv1a_hash(key) as usize;
+        self.find(hash, key).ok().map(|(_, pos)| pos)
+    }
+
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.get_index_of(key).is_some()
+    }
+
+    /// Removes a key by swapping the last entry into its place, returning the value
+    /// if the key was present. This is O(1) but perturbs the order.
+    pub fn swap_remove(&mut self, key: &K) -> Option<V> {
+        let pos = self.get_index_of(key)?;
+        self.swap_remove_index(pos).map(|(_, value)| value)
+    }
+
+    /// Removes a key by shifting every later entry down, returning the value if the key
+    /// was present. This keeps the order but is O(n).
+    pub fn shift_remove(&mut self, key: &K) -> Option<V> {
+        let pos = self.get_index_of(key)?;
+        self.shift_remove_index(pos).map(|(_, value)| value)
+    }
+}
+
+impl<K, V, const N: usize, const LOAD: usize> IndexMap<K, V, N, LOAD> {
+    #[inline]
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Maximum number of entries the map accepts, `N * LOAD / 100` rounded down
+    #[inline]
+    pub const fn max_len(&self) -> usize {
+        load_limit(N, LOAD)
+    }
+
+    #[inline]
+    pub const fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    #[inline]
+    pub const fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    #[inline]
+    pub const fn is_full(&self) -> bool {
+        self.entries.len() >= self.max_len()
+    }
+
+    /// Returns the entry at a position in insertion order
+    pub fn get_index(&self, index: usize) -> Option<(&K, &V)> {
+        self.entries
+            .get(index)
+            .map(|entry| (&entry.key, &entry.value))
+    }
+
+    /// Returns the entry at a position in insertion order, with a mutable value
+    pub fn get_index_mut(&mut self, index: usize) -> Option<(&K, &mut V)> {
+        self.entries
+            .get_mut(index)
+            .map(|entry| (&entry.key, &mut entry.value))
+    }
+
+    pub fn first(&self) -> Option<(&K, &V)> {
+        self.get_index(0)
+    }
+
+    pub fn last(&self) -> Option<(&K, &V)> {
+        self.entries.last().map(|entry| (&entry.key, &entry.value))
+    }
+
+    /// Removes and returns the last entry
+    pub fn pop(&mut self) -> Option<(K, V)> {
+        let pos = self.entries.len().checked_sub(1)?;
+        self.swap_remove_index(pos)
+    }
+
+    /// Removes the entry at `index` by swapping the last entry into its place
+    pub fn swap_remove_index(&mut self, index: usize) -> Option<(K, V)> {
+        if index >= self.entries.len() {
+            return None;
+        }
+
+        self.erase_index(index);
+
+        let last = self.entries.len() - 1;
+        if index != last {
+            let slot = self.slot_of(last);
+            self.indices[slot] = index;
+            self.entries.swap(index, last);
+        }
+
+        self.entries.pop().map(|entry| (entry.key, entry.value))
+    }
+
+    /// Removes the entry at `index` by shifting every later entry down
+    pub fn shift_remove_index(&mut self, index: usize) -> Option<(K, V)> {
+        if index >= self.entries.len() {
+            return None;
+        }
+
+        self.erase_index(index);
+        for pos in self.indices.iter_mut() {
+            if *pos != EMPTY && *pos > index {
+                *pos -= 1;
+            }
+        }
+
+        self.entries
+            .remove(index)
+            .map(|entry| (entry.key, entry.value))
+    }
+
+    /// Swaps the positions of two entries, panicking if either index is out of bounds
+    pub fn swap_indices(&mut self, a: usize, b: usize) {
+        if a == b {
+            assert!(a < self.entries.len(), "Index {} out of bounds", a);
+            return;
+        }
+
+        let slot_a = self.slot_of(a);
+        let slot_b = self.slot_of(b);
+        self.indices.swap(slot_a, slot_b);
+        self.entries.swap(a, b);
+    }
+
+    /// Sorts the entries with a comparator, keeping equal entries in their current order.
+    ///
+    /// This is an insertion sort since there's no allocator for a merge buffer,
+    /// so it's best suited to small or nearly-sorted maps.
+    pub fn sort_by<F>(&mut self, mut cmp: F)
+    where
+        F: FnMut(&K, &V, &K, &V) -> Ordering,
+    {
+        let map = RebuildOnDrop(self);
+        let entries = map.0.entries.as_mut_slice();
+        for i in 1..entries.len() {
+            let mut j = i;
+            while j > 0 {
+                let (a, b) = (&entries[j - 1], &entries[j]);
+                if cmp(&a.key, &a.value, &b.key, &b.value) != Ordering::Greater {
+                    break;
+                }
+                entries.swap(j - 1, j);
+                j -= 1;
+            }
+        }
+    }
+
+    /// Sorts the entries by key, keeping equal entries in their current order
+    pub fn sort_keys(&mut self)
+    where
+        K: Ord,
+    {
+        self.sort_by(|k1, _, k2, _| k1.cmp(k2));
+    }
+
+    /// Sorts the entries with a comparator, without preserving the order of equal entries
+    pub fn sort_unstable_by<F>(&mut self, mut cmp: F)
+    where
+        F: FnMut(&K, &V, &K, &V) -> Ordering,
+    {
+        let map = RebuildOnDrop(self);
+        map.0
+            .entries
+            .as_mut_slice()
+            .sort_unstable_by(|a, b| cmp(&a.key, &a.value, &b.key, &b.value));
+    }
+
+    /// Reverses the order of the entries
+    pub fn reverse(&mut self) {
+        self.entries.reverse();
+        self.rebuild_indices();
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.indices = [EMPTY; N];
+    }
+
+    pub fn iter(&self) -> IndexMapIter<'_, K, V> {
+        IndexMapIter {
+            inner: self.entries.as_slice().iter(),
+        }
+    }
+
+    pub fn iter_mut(&mut self) -> IndexMapIterMut<'_, K, V> {
+        IndexMapIterMut {
+            inner: self.entries.as_mut_slice().iter_mut(),
+        }
+    }
+
+    pub fn keys(&self) -> IndexMapKeys<'_, K, V> {
+        IndexMapKeys { inner: self.iter() }
+    }
+
+    pub fn values(&self) -> IndexMapValues<'_, K, V> {
+        IndexMapValues { inner: self.iter() }
+    }
+
+    pub fn values_mut(&mut self) -> IndexMapValuesMut<'_, K, V> {
+        IndexMapValuesMut {
+            inner: self.iter_mut(),
+        }
+    }
+
+    /// Returns the index slot pointing at a key, or the empty slot it would go in
+    fn find(&self, hash: usize, key: &K) -> Result<(usize, usize), usize>
+    where
+        K: PartialEq,
+    {
+        let mut slot = hash & (N - 1);
+
+        loop {
+            let pos = self.indices[slot];
+            if pos == EMPTY {
+                return Err(slot);
+            }
+
+            let entry = &self.entries[pos];
+            if entry.hash == hash && entry.key == *key {
+                return Ok((slot, pos));
+            }
+
+            slot = (slot + 1) & (N - 1);
+        }
+    }
+
+    /// Returns the index slot pointing at the entry at `pos`
+    fn slot_of(&self, pos: usize) -> usize {
+        let mut slot = self.entries[pos].hash & (N - 1);
+        while self.indices[slot] != pos {
+            slot = (slot + 1) & (N - 1);
+        }
+        slot
+    }
+
+    /// Removes the index slot for the entry at `pos`, shifting later slots in the
+    /// probe sequence back so lookups never need tombstones
+    fn erase_index(&mut self, pos: usize) {
+        let mut hole = self.slot_of(pos);
+        let mut slot = (hole + 1) & (N - 1);
+
+        loop {
+            let moved = self.indices[slot];
+            if moved == EMPTY {
+                break;
+            }
+
+            let ideal = self.entries[moved].hash & (N - 1);
+            let distance = slot.wrapping_sub(ideal) & (N - 1);
+            if distance >= slot.wrapping_sub(hole) & (N - 1) {
+                self.indices[hole] = moved;
+                hole = slot;
+            }
+
+            slot = (slot + 1) & (N - 1);
+        }
+
+        self.indices[hole] = EMPTY;
+    }
+
+    fn rebuild_indices(&mut self) {
+        self.indices = [EMPTY; N];
+        for (pos, entry) in self.entries.iter().enumerate() {
+            let mut slot = entry.hash & (N - 1);
+            while self.indices[slot] != EMPTY {
+                slot = (slot + 1) & (N - 1);
+            }
+            self.indices[slot] = pos;
+        }
+    }
+}
+
+/// Rebuilds the index when dropped, so it matches the entries even if a sort's
+/// comparator panics halfway through
+struct RebuildOnDrop<'a, K, V, const N: usize, const LOAD: usize>(&'a mut IndexMap<K, V, N, LOAD>);
+
+impl<K, V, const N: usize, const LOAD: usize> Drop for RebuildOnDrop<'_, K, V, N, LOAD> {
+    fn drop(&mut self) {
+        self.0.rebuild_indices();
+    }
+}
+
+impl<K, V, const N: usize, const LOAD: usize> Default for IndexMap<K, V, N, LOAD>
+where
+    K: Hash + PartialEq,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, V, const N: usize, const LOAD: usize> Index<usize> for IndexMap<K, V, N, LOAD> {
+    type Output = V;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        &self.entries[index].value
+    }
+}
+
+impl<K, V, const N: usize, const LOAD: usize> IndexMut<usize> for IndexMap<K, V, N, LOAD> {
+    fn index_mut(&mut self, index: usize) -> &mut Self::Output {
+        &mut self.entries[index].value
+    }
+}
+
+impl<K, V, const N: usize, const LOAD: usize> IntoIterator for IndexMap<K, V, N, LOAD> {
+    type Item = (K, V);
+    type IntoIter = IndexMapIntoIter<K, V, N>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IndexMapIntoIter {
+            inner: self.entries.into_iter(),
+        }
+    }
+}
+
+impl<'a, K, V, const N: usize, const LOAD: usize> IntoIterator for &'a IndexMap<K, V, N, LOAD> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = IndexMapIter<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, K, V, const N: usize, const LOAD: usize> IntoIterator for &'a mut IndexMap<K, V, N, LOAD> {
+    type Item = (&'a K, &'a mut V);
+    type IntoIter = IndexMapIterMut<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+/// Iterator over map entries in insertion order
+pub struct IndexMapIter<'a, K, V> {
+    inner: slice::Iter<'a, Entry<K, V>>,
+}
+
+impl<'a, K, V> Iterator for IndexMapIter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|entry| (&entry.key, &entry.value))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<'a, K, V> DoubleEndedIterator for IndexMapIter<'a, K, V> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner
+            .next_back()
+            .map(|entry| (&entry.key, &entry.value))
+    }
+}
+
+impl<'a, K, V> ExactSizeIterator for IndexMapIter<'a, K, V> {
+    fn len(&self) -> usize {
+        self.inner.len()
+    }
+}
+
+impl<'a, K, V> FusedIterator for IndexMapIter<'a, K, V> {}
+
+/// Mutable iterator over map entries in insertion order, keys are still shared
+pub struct IndexMapIterMut<'a, K, V> {
+    inner: slice::IterMut<'a, Entry<K, V>>,
+}
+
+impl<'a, K, V> Iterator for IndexMapIterMut<'a, K, V> {
+    type Item = (&'a K, &'a mut V);
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner
+            .next()
+            .map(|entry| (&entry.key, &mut entry.value))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<'a, K, V> DoubleEndedIterator for IndexMapIterMut<'a, K, V> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner
+            .next_back()
+            .map(|entry| (&entry.key, &mut entry.value))
+    }
+}
+
+impl<'a, K, V> ExactSizeIterator for IndexMapIterMut<'a, K, V> {
+    fn len(&self) -> usize {
+        self.inner.len()
+    }
+}
+
+impl<'a, K, V> FusedIterator for IndexMapIterMut<'a, K, V> {}
+
+pub struct IndexMapKeys<'a, K, V> {
+    inner: IndexMapIter<'a, K, V>,
+}
+
+impl<'a, K, V> Iterator for IndexMapKeys<'a, K, V> {
+    type Item = &'a K;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(key, _)| key)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<'a, K, V> DoubleEndedIterator for IndexMapKeys<'a, K, V> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back().map(|(key, _)| key)
+    }
+}
+
+impl<'a, K, V> ExactSizeIterator for IndexMapKeys<'a, K, V> {
+    fn len(&self) -> usize {
+        self.inner.len()
+    }
+}
+
+impl<'a, K, V> FusedIterator for IndexMapKeys<'a, K, V> {}
+
+pub struct IndexMapValues<'a, K, V> {
+    inner: IndexMapIter<'a, K, V>,
+}
+
+impl<'a, K, V> Iterator for IndexMapValues<'a, K, V> {
+    type Item = &'a V;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(_, value)| value)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<'a, K, V> DoubleEndedIterator for IndexMapValues<'a, K, V> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back().map(|(_, value)| value)
+    }
+}
+
+impl<'a, K, V> ExactSizeIterator for IndexMapValues<'a, K, V> {
+    fn len(&self) -> usize {
+        self.inner.len()
+    }
+}
+
+impl<'a, K, V> FusedIterator for IndexMapValues<'a, K, V> {}
+
+pub struct IndexMapValuesMut<'a, K, V> {
+    inner: IndexMapIterMut<'a, K, V>,
+}
+
+impl<'a, K, V> Iterator for IndexMapValuesMut<'a, K, V> {
+    type Item = &'a mut V;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(_, value)| value)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<'a, K, V> DoubleEndedIterator for IndexMapValuesMut<'a, K, V> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back().map(|(_, value)| value)
+    }
+}
+
+impl<'a, K, V> ExactSizeIterator for IndexMapValuesMut<'a, K, V> {
+    fn len(&self) -> usize {
+        self.inner.len()
+    }
+}
+
+impl<'a, K, V> FusedIterator for IndexMapValuesMut<'a, K, V> {}
+
+/// Owning iterator over map entries in insertion order
+pub struct IndexMapIntoIter<K, V, const N: usize> {
+    inner: FixedVecIntoIter<Entry<K, V>, N>,
+}
+
+impl<K, V, const N: usize> Iterator for IndexMapIntoIter<K, V, N> {
+    type Item = (K, V);
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|entry| (entry.key, entry.value))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<K, V, const N: usize> DoubleEndedIterator for IndexMapIntoIter<K, V, N> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back().map(|entry| (entry.key, entry.value))
+    }
+}
+
+impl<K, V, const N: usize> ExactSizeIterator for IndexMapIntoIter<K, V, N> {
+    fn len(&self) -> usize {
+        self.inner.len()
+    }
+}
+
+impl<K, V, const N: usize> FusedIterator for IndexMapIntoIter<K, V, N> {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IndexMapError {
+    Full,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::string::{String, ToString};
+    use std::vec::Vec;
+
+    fn keys<const N: usize>(map: &IndexMap<u32, u32, N>) -> Vec<u32> {
+        map.keys().copied().collect()
+    }
+
+    #[test]
+    fn test_insertion_order() {
+        let mut map = IndexMap::<u32, u32, 16>::new();
+        for key in [5, 3, 9, 1, 7] {
+            assert!(map.insert(key, key * 10).unwrap().is_none());
+        }
+
+        assert_eq!(keys(&map), [5, 3, 9, 1, 7]);
+        assert_eq!(map.get(&9), Some(&90));
+        assert_eq!(map.get_index_of(&1), Some(3));
+        assert_eq!(map.get_index(0), Some((&5, &50)));
+        assert_eq!(map.get(&4), None);
+        assert_eq!(map[2], 90);
+    }
+
+    #[test]
+    fn test_replace_keeps_position() {
+        let mut map = IndexMap::<u32, String, 8>::new();
+        map.insert(1, "one".to_string()).unwrap();
+        map.insert(2, "two".to_string()).unwrap();
+
+        let old = map.insert(1, "uno".to_string()).unwrap();
+        assert_eq!(old, Some("one".to_string()));
+        assert_eq!(map.get_index(0), Some((&1, &"uno".to_string())));
+        assert_eq!(map.len(), 2);
+    }
+
+    #[test]
+    fn test_full() {
+        let mut map = IndexMap::<u32, u32, 8>::new();
+        for i in 0..6 {
+            map.insert(i, i).unwrap();
+        }
+
+        assert!(map.is_full());
+        assert_eq!(map.insert(6, 6), Err(IndexMapError::Full));
+        assert_eq!(map.insert(3, 30), Ok(Some(3)));
+    }
+
+    #[test]
+    fn test_swap_remove() {
+        let mut map = IndexMap::<u32, u32, 16>::new();
+        for i in 0..6 {
+            map.insert(i, i).unwrap();
+        }
+
+        assert_eq!(map.swap_remove(&1), Some(1));
+        assert_eq!(keys(&map), [0, 5, 2, 3, 4]);
+        assert_eq!(map.swap_remove(&1), None);
+        for key in [0, 2, 3, 4, 5] {
+            assert_eq!(map.get(&key), Some(&key));
+        }
+        assert_eq!(map.get_index_of(&5), Some(1));
+    }
+
+    #[test]
+    fn test_shift_remove() {
+        let mut map = IndexMap::<u32, u32, 16>::new();
+        for i in 0..6 {
+            map.insert(i, i).unwrap();
+        }
+
+        assert_eq!(map.shift_remove(&1), Some(1));
+        assert_eq!(keys(&map), [0, 2, 3, 4, 5]);
+        for (pos, key) in [0, 2, 3, 4, 5].iter().enumerate() {
+            assert_eq!(map.get_index_of(key), Some(pos));
+        }
+        assert_eq!(map.pop(), Some((5, 5)));
+        assert_eq!(map.len(), 4);
+    }
+
+    #[test]
+    fn test_remove_collisions() {
+        // a small table forces long probe sequences, so removals have to shift slots back
+        let mut map = IndexMap::<u32, u32, 8>::new();
+        for round in 0..20 {
+            for i in 0..6 {
+                map.insert(round * 6 + i, i).unwrap();
+            }
+            for i in (0..6).step_by(2) {
+                assert_eq!(map.swap_remove(&(round * 6 + i)), Some(i));
+            }
+            for i in (1..6).step_by(2) {
+                assert_eq!(map.get(&(round * 6 + i)), Some(&i));
+                assert_eq!(map.shift_remove(&(round * 6 + i)), Some(i));
+            }
+            assert!(map.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_sort() {
+        let mut map = IndexMap::<u32, u32, 16>::new();
+        for (key, value) in [(4, 1), (2, 0), (8, 1), (6, 0)] {
+            map.insert(key, value).unwrap();
+        }
+
+        // stable: ties keep their insertion order
+        map.sort_by(|_, v1, _, v2| v1.cmp(v2));
+        assert_eq!(keys(&map), [2, 6, 4, 8]);
+
+        map.sort_keys();
+        assert_eq!(keys(&map), [2, 4, 6, 8]);
+
+        map.sort_unstable_by(|k1, _, k2, _| k2.cmp(k1));
+        assert_eq!(keys(&map), [8, 6, 4, 2]);
+        for key in [2, 4, 6, 8] {
+            assert!(map.contains_key(&key));
+        }
+        assert_eq!(map.get_index_of(&2), Some(3));
+    }
+
+    #[test]
+    fn test_sort_panic() {
+        use std::panic::{self, AssertUnwindSafe};
+
+        let mut map = IndexMap::<u32, u32, 16>::new();
+        for key in [5, 3, 8, 1, 9, 2] {
+            map.insert(key, key).unwrap();
+        }
+
+        let mut calls = 0;
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            map.sort_by(|k1, _, k2, _| {
+                calls += 1;
+                assert!(calls < 4, "comparator gave up");
+                k1.cmp(k2)
+            })
+        }));
+        assert!(result.is_err());
+
+        // the entries moved, and the index still finds each one where it ended up
+        assert_ne!(keys(&map), [5, 3, 8, 1, 9, 2]);
+        for (pos, key) in keys(&map).into_iter().enumerate() {
+            assert_eq!(map.get_index_of(&key), Some(pos));
+        }
+        assert_eq!(map.insert(3, 0), Ok(Some(3)));
+        assert_eq!(map.len(), 6);
+
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            map.sort_unstable_by(|_, _, _, _| panic!("comparator gave up"))
+        }));
+        assert!(result.is_err());
+        for (pos, key) in keys(&map).into_iter().enumerate() {
+            assert_eq!(map.get_index_of(&key), Some(pos));
+        }
+    }
+
+    #[test]
+    fn test_iterators() {
+        let mut map = IndexMap::<u32, u32, 16>::new();
+        for i in 0..5 {
+            map.insert(i, i).unwrap();
+        }
+
+        for (_, value) in map.iter_mut() {
+            *value *= 3;
+        }
+        let values: Vec<_> = map.values().copied().collect();
+        assert_eq!(values, [0, 3, 6, 9, 12]);
+
+        let reversed: Vec<_> = map.iter().rev().map(|(key, _)| *key).collect();
+        assert_eq!(reversed, [4, 3, 2, 1, 0]);
+
+        let mut owned = map.into_iter();
+        assert_eq!(owned.next_back(), Some((4, 12)));
+        assert_eq!(owned.len(), 4);
+        assert_eq!(owned.collect::<Vec<_>>(), [(0, 0), (1, 3), (2, 6), (3, 9)]);
+    }
+
+    #[test]
+    fn test_clear() {
+        let mut map = IndexMap::<String, u32, 8>::new();
+        map.insert("a".to_string(), 1).unwrap();
+        map.insert("b".to_string(), 2).unwrap();
+
+        map.clear();
+        assert!(map.is_empty());
+        assert_eq!(map.get(&"a".to_string()), None);
+
+        map.insert("c".to_string(), 3).unwrap();
+        assert_eq!(map.get_index(0), Some((&"c".to_string(), &3)));
+    }
+
+    #[test]
+    fn test_load_factor() {
+        let mut map = IndexMap::<u32, u32, 8, 50>::new();
+        assert_eq!(map.max_len(), 4);
+        for i in 0..4 {
+            map.insert(i, i).unwrap();
+        }
+        assert_eq!(map.insert(4, 4), Err(IndexMapError::Full));
+        assert_eq!(map.insert(2, 20), Ok(Some(2)));
+
+        let dense = IndexMap::<u32, u32, 128, 90>::new();
+        assert_eq!(dense.max_len(), 115);
+    }
+}
@@ -3,7 +3,10 @@
 #![allow(internal_features)]
 #![allow(incomplete_features)]
 #![forbid(unsafe_op_in_unsafe_fn)]
+#![feature(const_trait_impl)]
+#![feature(const_cmp)]
 #![feature(core_intrinsics)]
+#![feature(const_eval_select)]
 #![feature(generic_const_exprs)]
 #![doc = include_str!("../README.md")]
 
@@ -18,22 +21,53 @@
 //!
 //! =====================================================
 
-#[cfg(test)]
+#[cfg(any(test, feature = "std"))]
 extern crate std;
 
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
 pub mod btree;
+pub mod concurrent;
+pub mod countmap;
+pub mod cuckoo;
+pub mod cursor;
+pub mod fixedstring;
 pub mod fixedvec;
+pub mod flatmap;
 pub mod hashmap;
+pub mod hashset;
+pub mod indexmap;
+pub mod lru;
+pub mod multimap;
 pub mod queue;
 pub mod ring;
+pub mod sortedvec;
+#[cfg(feature = "alloc")]
+pub mod spillvec;
 pub mod stack;
+pub mod staticmap;
 
 pub use btree::BTree;
+pub use concurrent::ConcurrentHashMap;
+pub use countmap::CountMap;
+pub use cuckoo::CuckooMap;
+pub use cursor::FixedCursor;
+pub use fixedstring::FixedString;
 pub use fixedvec::FixedVec;
+pub use flatmap::FlatMap;
 pub use hashmap::HashMap;
+pub use hashset::HashSet;
+pub use indexmap::IndexMap;
+pub use lru::LruCache;
+pub use multimap::MultiMap;
 pub use queue::Queue;
 pub use ring::RingBuffer;
+pub use sortedvec::SortedVec;
+#[cfg(feature = "alloc")]
+pub use spillvec::SpillVec;
 pub use stack::Stack;
+pub use staticmap::StaticMap;
 
 #[macro_export]
 macro_rules! unlikely {
@@ -48,3 +82,17 @@ macro_rules! likely {
         core::intrinsics::likely($cond)
     };
 }
+
+/// Drops `value` at runtime, or forgets it during const evaluation, where a generic
+/// destructor can't run
+pub(crate) const fn discard<T>(value: T) {
+    const fn forget<T>(value: T) {
+        core::mem::forget(value)
+    }
+
+    fn drop<T>(value: T) {
+        core::mem::drop(value)
+    }
+
+    core::intrinsics::const_eval_select((value,), forget, drop)
+}
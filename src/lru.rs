@@ -0,0 +1,596 @@
+use core::hash::Hash;
+use core::iter::FusedIterator;
+use core::mem::MaybeUninit;
+use core::ptr;
+
+use crate::hashmap::{DEFAULT_LOAD, Probe, fnv1a_hash, load_limit, probe_distance};
+
+const NIL: usize = usize::MAX;
+
+/// Fixed capacity least-recently-used cache.
+///
+/// Entries live in an open addressing table probed the same way as
+/// [`HashMap`](crate::HashMap)'s, threaded onto a doubly linked recency list by bucket
+/// index. A full cache evicts on every miss, so removals shift later entries back
+/// instead of leaving tombstones, which keeps every operation O(1) no matter how long
+/// the cache has been churning.
+///
+/// Capacity must be a power of 2. `LOAD` is the maximum load factor as a percentage
+/// of `N` (75% by default), and must leave at least one bucket empty.
+pub struct LruCache<K, V, const N: usize, const LOAD: usize = DEFAULT_LOAD> {
+    buckets: [Bucket<K, V>; N],
+    links: [Link; N],
+    head: usize,
+    tail: usize,
+    len: usize,
+}
+
+struct Bucket<K, V> {
+    key: MaybeUninit<K>,
+    value: MaybeUninit<V>,
+}
+
+impl<K, V> Bucket<K, V> {
+    const fn new() -> Self {
+        Self {
+            key: MaybeUninit::uninit(),
+            value: MaybeUninit::uninit(),
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Link {
+    prev: usize,
+    next: usize,
+    hash: usize,
+    occupied: bool,
+}
+
+impl Link {
+    const EMPTY: Self = Self {
+        prev: NIL,
+        next: NIL,
+        hash: 0,
+        occupied: false,
+    };
+}
+
+impl<K, V, const N: usize, const LOAD: usize> LruCache<K, V, N, LOAD>
+where
+    K: Hash + PartialEq,
+{
+    const LOAD_CHECK: () = {
+        assert!(LOAD > 0, "LruCache load factor must be greater than 0");
+        assert!(LOAD < 100, "LruCache load factor must leave a bucket empty");
+    };
+
+    /// Panics if `N` is not a power of 2, or too small to hold an entry at `LOAD`
+    /// (`N = 1`, or `N = 2` below a 50% load factor)
+    ///
+    /// Fails to compile if `LOAD` isn't between 1 and 99:
+    ///
+    /// ```compile_fail
+    /// use sakurai::LruCache;
+    ///
+    /// let cache = LruCache::<u8, u8, 8, 100>::new();
+    /// ```
+    pub const fn new() -> Self {
+        assert!(N > 0, "LruCache size must be greater than 0");
+        assert!(N.is_power_of_two(), "LruCache size must be a power of 2");
+        let () = Self::LOAD_CHECK;
+        assert!(
+            load_limit(N, LOAD) > 0,
+            "LruCache size must leave room for an entry"
+        );
+
+        Self {
+            buckets: [const { Bucket::new() }; N],
+            links: [Link::EMPTY; N],
+            head: NIL,
+            tail: NIL,
+            len: 0,
+        }
+    }
+
+    /// Get a reference to a value for a given key, marking it as most recently used
+    pub fn get(&mut self, key: &K) -> Option<&V> {
+        let index = self.find(key)?;
+        self.promote(index);
+        Some(unsafe { self.buckets[index].value.assume_init_ref() })
+    }
+
+    /// Get a mut reference to a value for a given key, marking it as most recently used
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        let index = self.find(key)?;
+        self.promote(index);
+        Some(unsafe { self.buckets[index].value.assume_init_mut() })
+    }
+
+    /// Get a reference to a value for a given key without touching its recency
+    pub fn peek(&self, key: &K) -> Option<&V> {
+        self.find(key)
+            .map(|index| unsafe { self.buckets[index].value.assume_init_ref() })
+    }
+
+    pub fn contains(&self, key: &K) -> bool {
+        self.find(key).is_some()
+    }
+
+    /// Inserts a key-value pair as the most recently used entry.
+    ///
+    /// If the key was already present, its value is replaced and the old one is
+    /// returned in [`LruPut::Replaced`]. Otherwise, if the cache is full, the least
+    /// recently used pair is evicted to make room and returned in [`LruPut::Evicted`].
+    pub fn put(&mut self, key: K, value: V) -> LruPut<K, V> {
+        let hash = fnv1a_hash(&key) as usize;
+
+        if let Some(index) = self.find_hashed(hash, &key) {
+            self.promote(index);
+            let bucket = &mut self.buckets[index];
+            let old_value = unsafe { ptr::replace(bucket.value.as_mut_ptr(), value) };
+            return LruPut::Replaced(old_value);
+        }
+
+        let mut outcome = LruPut::Inserted;
+        if self.is_full()
+            && let Some((old_key, old_value)) = self.pop_lru()
+        {
+            outcome = LruPut::Evicted(old_key, old_value);
+        }
+
+        // the load factor always leaves an empty bucket
+        let index = Probe::<N>::new(hash)
+            .find(|&index| !self.links[index].occupied)
+            .expect("LruCache has no free bucket");
+
+        self.buckets[index].key.write(key);
+        self.buckets[index].value.write(value);
+        self.links[index] = Link {
+            prev: NIL,
+            next: NIL,
+            hash,
+            occupied: true,
+        };
+        self.push_front(index);
+        self.len += 1;
+
+        outcome
+    }
+
+    /// Removes a key from the cache, returning its value if it was present
+    pub fn pop(&mut self, key: &K) -> Option<V> {
+        let index = self.find(key)?;
+        let (_, value) = unsafe { self.take(index) };
+        Some(value)
+    }
+
+    fn find(&self, key: &K) -> Option<usize> {
+        self.find_hashed(fnv1a_hash(key) as usize, key)
+    }
+
+    fn find_hashed(&self, hash: usize, key: &K) -> Option<usize> {
+        for index in Probe::<N>::new(hash) {
+            if !self.links[index].occupied {
+                break;
+            }
+            if self.links[index].hash == hash {
+                let bucket_key = unsafe { self.buckets[index].key.assume_init_ref() };
+                if bucket_key == key {
+                    return Some(index);
+                }
+            }
+        }
+
+        None
+    }
+}
+
+impl<K, V, const N: usize, const LOAD: usize> LruCache<K, V, N, LOAD> {
+    #[inline]
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Maximum number of entries the cache holds before it starts evicting,
+    /// `N * LOAD / 100` rounded down
+    #[inline]
+    pub const fn max_len(&self) -> usize {
+        load_limit(N, LOAD)
+    }
+
+    #[inline]
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    #[inline]
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    #[inline]
+    pub const fn is_full(&self) -> bool {
+        self.len >= self.max_len()
+    }
+
+    /// Removes and returns the least recently used pair
+    pub fn pop_lru(&mut self) -> Option<(K, V)> {
+        if self.tail == NIL {
+            return None;
+        }
+        Some(unsafe { self.take(self.tail) })
+    }
+
+    /// Returns the least recently used pair without touching its recency
+    pub fn peek_lru(&self) -> Option<(&K, &V)> {
+        if self.tail == NIL {
+            return None;
+        }
+        let bucket = &self.buckets[self.tail];
+        Some(unsafe { (bucket.key.assume_init_ref(), bucket.value.assume_init_ref()) })
+    }
+
+    pub fn clear(&mut self) {
+        while self.pop_lru().is_some() {}
+    }
+
+    /// Iterates from the most to the least recently used pair
+    pub fn iter(&self) -> LruCacheIter<'_, K, V, N, LOAD> {
+        LruCacheIter {
+            cache: self,
+            front: self.head,
+            back: self.tail,
+            remaining: self.len,
+        }
+    }
+
+    fn promote(&mut self, index: usize) {
+        if self.head != index {
+            self.unlink(index);
+            self.push_front(index);
+        }
+    }
+
+    fn push_front(&mut self, index: usize) {
+        self.links[index].prev = NIL;
+        self.links[index].next = self.head;
+        if self.head != NIL {
+            self.links[self.head].prev = index;
+        } else {
+            self.tail = index;
+        }
+        self.head = index;
+    }
+
+    fn unlink(&mut self, index: usize) {
+        let Link { prev, next, .. } = self.links[index];
+        if prev != NIL {
+            self.links[prev].next = next;
+        } else {
+            self.head = next;
+        }
+        if next != NIL {
+            self.links[next].prev = prev;
+        } else {
+            self.tail = prev;
+        }
+    }
+
+    /// Moves the pair out of an occupied bucket, then shifts later entries in the
+    /// probe sequence back into the hole
+    ///
+    /// # Safety
+    ///
+    /// The bucket at `index` must be occupied
+    unsafe fn take(&mut self, index: usize) -> (K, V) {
+        self.unlink(index);
+        self.links[index].occupied = false;
+        self.len -= 1;
+
+        let bucket = &self.buckets[index];
+        let pair = unsafe {
+            (
+                ptr::read(bucket.key.as_ptr()),
+                ptr::read(bucket.value.as_ptr()),
+            )
+        };
+
+        // an entry moves back if the hole is no further along its probe sequence than
+        // where it sits now
+        let mut hole = index;
+        for current in Probe::<N>::new(index + 1) {
+            if !self.links[current].occupied {
+                break;
+            }
+            let hash = self.links[current].hash;
+            if probe_distance::<N>(hash, current) >= probe_distance::<N>(hole, current) {
+                self.relocate(current, hole);
+                hole = current;
+            }
+        }
+
+        pair
+    }
+
+    /// Moves an occupied bucket into an empty one, repointing its list neighbours
+    fn relocate(&mut self, from: usize, to: usize) {
+        unsafe {
+            let src = &self.buckets[from] as *const Bucket<K, V>;
+            let dst = &mut self.buckets[to] as *mut Bucket<K, V>;
+            ptr::copy_nonoverlapping(src, dst, 1);
+        }
+
+        let link = self.links[from];
+        self.links[to] = link;
+        self.links[from].occupied = false;
+
+        if link.prev != NIL {
+            self.links[link.prev].next = to;
+        } else {
+            self.head = to;
+        }
+        if link.next != NIL {
+            self.links[link.next].prev = to;
+        } else {
+            self.tail = to;
+        }
+    }
+}
+
+impl<K, V, const N: usize, const LOAD: usize> Default for LruCache<K, V, N, LOAD>
+where
+    K: Hash + PartialEq,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, V, const N: usize, const LOAD: usize> Drop for LruCache<K, V, N, LOAD> {
+    fn drop(&mut self) {
+        for (bucket, link) in self.buckets.iter_mut().zip(self.links.iter()) {
+            if link.occupied {
+                unsafe {
+                    bucket.key.assume_init_drop();
+                    bucket.value.assume_init_drop();
+                }
+            }
+        }
+    }
+}
+
+impl<'a, K, V, const N: usize, const LOAD: usize> IntoIterator for &'a LruCache<K, V, N, LOAD> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = LruCacheIter<'a, K, V, N, LOAD>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Iterator over cache entries in recency order, see [`LruCache::iter`]
+pub struct LruCacheIter<'a, K, V, const N: usize, const LOAD: usize = DEFAULT_LOAD> {
+    cache: &'a LruCache<K, V, N, LOAD>,
+    front: usize,
+    back: usize,
+    remaining: usize,
+}
+
+impl<'a, K, V, const N: usize, const LOAD: usize> LruCacheIter<'a, K, V, N, LOAD> {
+    #[inline]
+    fn pair(&self, index: usize) -> (&'a K, &'a V) {
+        let bucket = &self.cache.buckets[index];
+        unsafe { (bucket.key.assume_init_ref(), bucket.value.assume_init_ref()) }
+    }
+}
+
+impl<'a, K, V, const N: usize, const LOAD: usize> Iterator for LruCacheIter<'a, K, V, N, LOAD> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+
+        let index = self.front;
+        self.front = self.cache.links[index].next;
+        self.remaining -= 1;
+        Some(self.pair(index))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'a, K, V, const N: usize, const LOAD: usize> DoubleEndedIterator
+    for LruCacheIter<'a, K, V, N, LOAD>
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+
+        let index = self.back;
+        self.back = self.cache.links[index].prev;
+        self.remaining -= 1;
+        Some(self.pair(index))
+    }
+}
+
+impl<'a, K, V, const N: usize, const LOAD: usize> ExactSizeIterator
+    for LruCacheIter<'a, K, V, N, LOAD>
+{
+    fn len(&self) -> usize {
+        self.remaining
+    }
+}
+
+impl<'a, K, V, const N: usize, const LOAD: usize> FusedIterator
+    for LruCacheIter<'a, K, V, N, LOAD>
+{
+}
+
+/// Returned by [`LruCache::put`], telling an update apart from an eviction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LruPut<K, V> {
+    /// The key was new and the cache had room for it
+    Inserted,
+    /// The key was already present, with this value
+    Replaced(V),
+    /// The key was new, and this least recently used pair was evicted for it
+    Evicted(K, V),
+}
+
+impl<K, V> LruPut<K, V> {
+    /// Returns the evicted pair, if any
+    pub fn evicted(self) -> Option<(K, V)> {
+        match self {
+            LruPut::Evicted(key, value) => Some((key, value)),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::rc::Rc;
+    use std::string::{String, ToString};
+    use std::vec::Vec;
+
+    fn keys<const N: usize>(cache: &LruCache<u32, u32, N>) -> Vec<u32> {
+        cache.iter().map(|(key, _)| *key).collect()
+    }
+
+    #[test]
+    fn test_put_get() {
+        let mut cache = LruCache::<u32, String, 8>::new();
+
+        assert_eq!(cache.put(1, "one".to_string()), LruPut::Inserted);
+        assert_eq!(cache.put(2, "two".to_string()), LruPut::Inserted);
+        assert_eq!(cache.get(&1), Some(&"one".to_string()));
+        assert_eq!(cache.get(&3), None);
+        assert_eq!(cache.len(), 2);
+
+        let replaced = cache.put(1, "uno".to_string());
+        assert_eq!(replaced, LruPut::Replaced("one".to_string()));
+        assert_eq!(cache.peek(&1), Some(&"uno".to_string()));
+        assert_eq!(cache.len(), 2);
+    }
+
+    #[test]
+    fn test_eviction_order() {
+        let mut cache = LruCache::<u32, u32, 4>::new();
+        for i in 0..3 {
+            cache.put(i, i);
+        }
+        assert!(cache.is_full());
+
+        // touching 0 makes 1 the least recently used
+        cache.get(&0);
+        assert_eq!(cache.put(3, 3), LruPut::Evicted(1, 1));
+        assert_eq!(keys(&cache), [3, 0, 2]);
+
+        // peeking doesn't count as a use
+        cache.peek(&2);
+        assert_eq!(cache.put(4, 4).evicted(), Some((2, 2)));
+        assert_eq!(keys(&cache), [4, 3, 0]);
+    }
+
+    #[test]
+    fn test_pop_lru() {
+        let mut cache = LruCache::<u32, u32, 8>::new();
+        for i in 0..5 {
+            cache.put(i, i * 10);
+        }
+
+        assert_eq!(cache.peek_lru(), Some((&0, &0)));
+        assert_eq!(cache.pop_lru(), Some((0, 0)));
+        assert_eq!(cache.pop_lru(), Some((1, 10)));
+        assert_eq!(cache.pop(&3), Some(30));
+        assert_eq!(keys(&cache), [4, 2]);
+    }
+
+    #[test]
+    fn test_iter_both_ends() {
+        let mut cache = LruCache::<u32, u32, 8>::new();
+        for i in 0..5 {
+            cache.put(i, i);
+        }
+
+        let lru_first: Vec<_> = cache.iter().rev().map(|(key, _)| *key).collect();
+        assert_eq!(lru_first, [0, 1, 2, 3, 4]);
+        assert_eq!(cache.iter().len(), 5);
+    }
+
+    #[test]
+    fn test_churn() {
+        // a small table under constant eviction keeps shifting entries around
+        let mut cache = LruCache::<u32, u32, 8>::new();
+        for i in 0..1000 {
+            cache.put(i, i);
+            if i % 3 == 0 && i > 0 {
+                assert_eq!(cache.get(&(i - 1)), Some(&(i - 1)));
+            }
+            assert!(cache.len() <= 6);
+        }
+
+        assert_eq!(cache.len(), 6);
+        for (key, value) in cache.iter() {
+            assert_eq!(key, value);
+            assert_eq!(cache.peek(key), Some(value));
+        }
+        assert!(cache.contains(&999));
+    }
+
+    #[test]
+    fn test_drop() {
+        let tracker = Rc::new(());
+        {
+            let mut cache = LruCache::<u32, Rc<()>, 8>::new();
+            for i in 0..20 {
+                cache.put(i, tracker.clone());
+            }
+            assert_eq!(Rc::strong_count(&tracker), 7);
+
+            cache.clear();
+            assert_eq!(Rc::strong_count(&tracker), 1);
+            cache.put(1, tracker.clone());
+        }
+        assert_eq!(Rc::strong_count(&tracker), 1);
+    }
+
+    #[test]
+    fn test_load_factor() {
+        let mut cache = LruCache::<u32, u32, 8, 50>::new();
+        assert_eq!(cache.max_len(), 4);
+        for i in 0..4 {
+            cache.put(i, i);
+        }
+        assert!(cache.is_full());
+        assert_eq!(cache.put(4, 4), LruPut::Evicted(0, 0));
+        assert_eq!(cache.len(), 4);
+    }
+
+    #[test]
+    fn test_smallest_capacity() {
+        let mut cache = LruCache::<u32, u32, 2>::new();
+        assert_eq!(cache.max_len(), 1);
+
+        assert_eq!(cache.put(1, 1), LruPut::Inserted);
+        assert_eq!(cache.put(2, 2), LruPut::Evicted(1, 1));
+        assert_eq!(cache.put(1, 10), LruPut::Evicted(2, 2));
+        assert_eq!(cache.put(1, 11), LruPut::Replaced(10));
+        assert_eq!(cache.len(), 1);
+        assert_eq!(cache.get(&1), Some(&11));
+        assert_eq!(cache.get(&2), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "room for an entry")]
+    fn test_capacity_too_small() {
+        LruCache::<u32, u32, 1>::new();
+    }
+}
@@ -0,0 +1,484 @@
+use core::hash::Hash;
+use core::iter::FusedIterator;
+use core::mem::{self, MaybeUninit};
+use core::ptr;
+
+use crate::hashmap::{HashMap, HashMapIter};
+
+const NIL: usize = usize::MAX;
+
+/// Fixed capacity map from each key to any number of values, backed by the open
+/// addressing [`HashMap`].
+///
+/// Values live in a shared pool of `N` slots, chained per key in insertion order, so
+/// the map holds up to `N` values in total, spread over up to 3/4 of `N` distinct
+/// keys. Capacity must be a power of 2.
+pub struct MultiMap<K, V, const N: usize> {
+    keys: HashMap<K, Chain, N>,
+    values: [MaybeUninit<V>; N],
+    next: [usize; N],
+    free: usize,
+    len: usize,
+}
+
+/// Slots of one key's values, linked through `next`
+#[derive(Clone, Copy)]
+struct Chain {
+    head: usize,
+    tail: usize,
+    len: usize,
+}
+
+impl<K, V, const N: usize> MultiMap<K, V, N>
+where
+    K: Hash + PartialEq,
+{
+    /// Panics if `N` is not a power of 2 (or is 0)
+    pub const fn new() -> Self {
+        Self {
+            keys: HashMap::new(),
+            values: [const { MaybeUninit::uninit() }; N],
+            next: free_list(),
+            free: 0,
+            len: 0,
+        }
+    }
+
+    /// Number of distinct keys
+    #[inline]
+    pub const fn key_count(&self) -> usize {
+        self.keys.len()
+    }
+
+    /// Appends a value to the ones stored for `key`
+    pub fn insert(&mut self, key: K, value: V) -> Result<(), MultiMapError> {
+        if self.free == NIL {
+            return Err(MultiMapError::Full);
+        }
+
+        match self.keys.get_mut(&key) {
+            Some(chain) => {
+                let slot = self.free;
+                self.free = self.next[slot];
+                self.values[slot].write(value);
+                self.next[slot] = NIL;
+
+                self.next[chain.tail] = slot;
+                chain.tail = slot;
+                chain.len += 1;
+            }
+            None => {
+                if self.keys.is_full() {
+                    return Err(MultiMapError::Full);
+                }
+
+                let slot = self.free;
+                self.free = self.next[slot];
+                self.values[slot].write(value);
+                self.next[slot] = NIL;
+
+                let chain = Chain {
+                    head: slot,
+                    tail: slot,
+                    len: 1,
+                };
+                if self.keys.try_insert(key, chain).is_err() {
+                    unreachable!("MultiMap key table checked for room");
+                }
+            }
+        }
+
+        self.len += 1;
+        Ok(())
+    }
+
+    /// Iterates over the values stored for `key`, oldest first
+    pub fn get(&self, key: &K) -> MultiMapValues<'_, V, N> {
+        match self.keys.get(key) {
+            Some(chain) => self.chain_values(chain),
+            None => self.no_values(),
+        }
+    }
+
+    /// Number of values stored for `key`
+    pub fn count(&self, key: &K) -> usize {
+        self.keys.get(key).map_or(0, |chain| chain.len)
+    }
+
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.keys.contains_key(key)
+    }
+
+    /// Removes the oldest value stored for `key`, dropping the key along with its last value
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let chain = self.keys.get_mut(key)?;
+        let slot = chain.head;
+        chain.head = self.next[slot];
+        chain.len -= 1;
+
+        if chain.len == 0 {
+            self.keys.remove(key);
+        }
+
+        Some(unsafe { self.release(slot) })
+    }
+
+    pub fn clear(&mut self) {
+        // unlink every value before dropping any, so a panicking destructor can't leave
+        // `Drop` to drop them again
+        let keys = mem::take(&mut self.keys);
+        let next = mem::replace(&mut self.next, free_list());
+        self.free = 0;
+        self.len = 0;
+        drop_chains(&mut self.values, &keys, &next);
+    }
+
+    /// Removes a key and all of its values, returning how many there were
+    pub fn remove_all(&mut self, key: &K) -> usize {
+        match self.keys.remove(key) {
+            Some(chain) => {
+                self.release_chain(chain);
+                chain.len
+            }
+            None => 0,
+        }
+    }
+}
+
+impl<K, V, const N: usize> MultiMap<K, V, N> {
+    #[inline]
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Number of values across all keys
+    #[inline]
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    #[inline]
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    #[inline]
+    pub const fn is_full(&self) -> bool {
+        self.len >= N
+    }
+
+    /// Iterates over every key-value pair, with each key's values grouped together
+    pub fn iter(&self) -> MultiMapIter<'_, K, V, N> {
+        MultiMapIter {
+            map: self,
+            keys: self.keys.iter(),
+            key: None,
+            values: self.no_values(),
+            remaining: self.len,
+        }
+    }
+
+    fn chain_values(&self, chain: &Chain) -> MultiMapValues<'_, V, N> {
+        MultiMapValues {
+            values: &self.values,
+            next: &self.next,
+            slot: chain.head,
+            remaining: chain.len,
+        }
+    }
+
+    fn no_values(&self) -> MultiMapValues<'_, V, N> {
+        MultiMapValues {
+            values: &self.values,
+            next: &self.next,
+            slot: NIL,
+            remaining: 0,
+        }
+    }
+
+    /// Moves a value out of its slot and returns the slot to the free list
+    ///
+    /// # Safety
+    ///
+    /// The slot must hold a value
+    unsafe fn release(&mut self, slot: usize) -> V {
+        self.next[slot] = self.free;
+        self.free = slot;
+        self.len -= 1;
+        unsafe { ptr::read(self.values[slot].as_ptr()) }
+    }
+
+    fn release_chain(&mut self, chain: Chain) {
+        let mut slot = chain.head;
+        for _ in 0..chain.len {
+            let next = self.next[slot];
+            drop(unsafe { self.release(slot) });
+            slot = next;
+        }
+    }
+}
+
+/// Drops every value chained from `keys`, leaving the chains untouched
+fn drop_chains<K, V, const N: usize>(
+    values: &mut [MaybeUninit<V>; N],
+    keys: &HashMap<K, Chain, N>,
+    next: &[usize; N],
+) {
+    for (_, chain) in keys.iter() {
+        let mut slot = chain.head;
+        for _ in 0..chain.len {
+            unsafe { values[slot].assume_init_drop() };
+            slot = next[slot];
+        }
+    }
+}
+
+/// Every slot free, each pointing at the next
+const fn free_list<const N: usize>() -> [usize; N] {
+    let mut next = [NIL; N];
+    let mut i = 0;
+    while i + 1 < N {
+        next[i] = i + 1;
+        i += 1;
+    }
+    next
+}
+
+impl<K, V, const N: usize> Default for MultiMap<K, V, N>
+where
+    K: Hash + PartialEq,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, V, const N: usize> Drop for MultiMap<K, V, N> {
+    fn drop(&mut self) {
+        drop_chains(&mut self.values, &self.keys, &self.next);
+    }
+}
+
+impl<'a, K, V, const N: usize> IntoIterator for &'a MultiMap<K, V, N> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = MultiMapIter<'a, K, V, N>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Iterator over the values of one key, see [`MultiMap::get`]
+pub struct MultiMapValues<'a, V, const N: usize> {
+    values: &'a [MaybeUninit<V>; N],
+    next: &'a [usize; N],
+    slot: usize,
+    remaining: usize,
+}
+
+impl<'a, V, const N: usize> Iterator for MultiMapValues<'a, V, N> {
+    type Item = &'a V;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+
+        let slot = self.slot;
+        self.slot = self.next[slot];
+        self.remaining -= 1;
+        Some(unsafe { self.values[slot].assume_init_ref() })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'a, V, const N: usize> ExactSizeIterator for MultiMapValues<'a, V, N> {
+    fn len(&self) -> usize {
+        self.remaining
+    }
+}
+
+impl<'a, V, const N: usize> FusedIterator for MultiMapValues<'a, V, N> {}
+
+/// Iterator over every key-value pair, see [`MultiMap::iter`]
+pub struct MultiMapIter<'a, K, V, const N: usize> {
+    map: &'a MultiMap<K, V, N>,
+    keys: HashMapIter<'a, K, Chain, N>,
+    key: Option<&'a K>,
+    values: MultiMapValues<'a, V, N>,
+    remaining: usize,
+}
+
+impl<'a, K, V, const N: usize> Iterator for MultiMapIter<'a, K, V, N> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let (Some(key), Some(value)) = (self.key, self.values.next()) {
+                self.remaining -= 1;
+                return Some((key, value));
+            }
+
+            let (key, chain) = self.keys.next()?;
+            self.key = Some(key);
+            self.values = self.map.chain_values(chain);
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'a, K, V, const N: usize> ExactSizeIterator for MultiMapIter<'a, K, V, N> {
+    fn len(&self) -> usize {
+        self.remaining
+    }
+}
+
+impl<'a, K, V, const N: usize> FusedIterator for MultiMapIter<'a, K, V, N> {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MultiMapError {
+    Full,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::rc::Rc;
+    use std::vec::Vec;
+
+    #[test]
+    fn test_insert_get() {
+        let mut subscribers = MultiMap::<&str, u32, 16>::new();
+        subscribers.insert("news", 1).unwrap();
+        subscribers.insert("sports", 2).unwrap();
+        subscribers.insert("news", 3).unwrap();
+        subscribers.insert("news", 4).unwrap();
+
+        assert_eq!(
+            subscribers.get(&"news").copied().collect::<Vec<_>>(),
+            [1, 3, 4]
+        );
+        assert_eq!(subscribers.get(&"sports").len(), 1);
+        assert_eq!(subscribers.get(&"weather").next(), None);
+        assert_eq!(subscribers.count(&"news"), 3);
+        assert_eq!(subscribers.len(), 4);
+        assert_eq!(subscribers.key_count(), 2);
+    }
+
+    #[test]
+    fn test_remove() {
+        let mut map = MultiMap::<u32, u32, 8>::new();
+        for value in 0..3 {
+            map.insert(1, value).unwrap();
+        }
+        map.insert(2, 10).unwrap();
+
+        assert_eq!(map.remove(&1), Some(0));
+        assert_eq!(map.get(&1).copied().collect::<Vec<_>>(), [1, 2]);
+        assert_eq!(map.remove_all(&1), 2);
+        assert!(!map.contains_key(&1));
+        assert_eq!(map.remove(&1), None);
+
+        assert_eq!(map.remove(&2), Some(10));
+        assert!(!map.contains_key(&2));
+        assert!(map.is_empty());
+    }
+
+    #[test]
+    fn test_full() {
+        let mut map = MultiMap::<u32, u32, 4>::new();
+        for value in 0..4 {
+            map.insert(value % 2, value).unwrap();
+        }
+        assert!(map.is_full());
+        assert_eq!(map.insert(0, 9), Err(MultiMapError::Full));
+
+        // slots freed by one key are reused by another
+        map.remove_all(&0);
+        map.insert(1, 4).unwrap();
+        map.insert(2, 5).unwrap();
+        assert_eq!(map.get(&1).copied().collect::<Vec<_>>(), [1, 3, 4]);
+
+        // the key table fills up before the value pool does
+        let mut keys = MultiMap::<u32, u32, 4>::new();
+        for key in 0..3 {
+            keys.insert(key, key).unwrap();
+        }
+        assert_eq!(keys.insert(3, 3), Err(MultiMapError::Full));
+        keys.insert(0, 3).unwrap();
+    }
+
+    #[test]
+    fn test_iter() {
+        let mut map = MultiMap::<u32, u32, 16>::new();
+        for value in 0..9 {
+            map.insert(value % 3, value).unwrap();
+        }
+
+        assert_eq!(map.iter().len(), 9);
+
+        let mut pairs: Vec<_> = map.iter().map(|(k, v)| (*k, *v)).collect();
+        pairs.sort();
+        let mut expected: Vec<_> = (0..9).map(|v| (v % 3, v)).collect();
+        expected.sort();
+        assert_eq!(pairs, expected);
+    }
+
+    #[test]
+    fn test_drop() {
+        let tracker = Rc::new(());
+        {
+            let mut map = MultiMap::<u32, Rc<()>, 8>::new();
+            for i in 0..6 {
+                map.insert(i % 2, tracker.clone()).unwrap();
+            }
+            map.remove(&0);
+            map.remove_all(&1);
+            assert_eq!(Rc::strong_count(&tracker), 3);
+
+            map.clear();
+            assert_eq!(Rc::strong_count(&tracker), 1);
+            for i in 0..8 {
+                map.insert(i % 3, tracker.clone()).unwrap();
+            }
+        }
+        assert_eq!(Rc::strong_count(&tracker), 1);
+    }
+
+    #[test]
+    fn test_clear_panic() {
+        use core::cell::Cell;
+        use std::panic::{self, AssertUnwindSafe};
+
+        struct Bomb<'a>(&'a Cell<usize>, bool);
+        impl Drop for Bomb<'_> {
+            fn drop(&mut self) {
+                self.0.set(self.0.get() + 1);
+                if self.1 {
+                    panic!("boom");
+                }
+            }
+        }
+
+        let drops = Cell::new(0);
+        let mut map = MultiMap::<u32, Bomb, 8>::new();
+        for i in 0..6 {
+            map.insert(i % 2, Bomb(&drops, i == 2)).unwrap();
+        }
+
+        let result = panic::catch_unwind(AssertUnwindSafe(|| map.clear()));
+        assert!(result.is_err());
+        assert!(map.is_empty());
+        assert_eq!(map.key_count(), 0);
+
+        // whatever the panic skipped is leaked, never dropped twice
+        drop(map);
+        assert!(drops.get() <= 6);
+    }
+}
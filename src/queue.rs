@@ -1,35 +1,42 @@
 use core::cell::UnsafeCell;
-use core::mem::MaybeUninit;
+use core::mem::{self, MaybeUninit};
 use core::ptr;
-use core::sync::atomic::{AtomicUsize, Ordering};
+use core::sync::atomic::Ordering;
+
+use crate::fixedvec::sealed::AtomicLength;
+use crate::fixedvec::{Length, LengthFits};
 
 /// Fixed capacity queue for single producer single consumer concurrent operations
 /// without locks.
+///
+/// The head and tail indices are stored as atomics of `L`, see [`Length`].
 #[repr(align(64))]
-pub struct Queue<T, const N: usize> {
+pub struct Queue<T, const N: usize, L: Length = usize> {
     data: [UnsafeCell<MaybeUninit<T>>; N],
-    head: AtomicUsize,
-    tail: AtomicUsize,
+    head: L::Atomic,
+    tail: L::Atomic,
 }
 
-pub struct Producer<'a, T, const N: usize> {
-    queue: &'a Queue<T, N>,
+pub struct Producer<'a, T, const N: usize, L: Length = usize> {
+    queue: &'a Queue<T, N, L>,
 }
 
-pub struct Consumer<'a, T, const N: usize> {
-    queue: &'a Queue<T, N>,
+pub struct Consumer<'a, T, const N: usize, L: Length = usize> {
+    queue: &'a Queue<T, N, L>,
 }
 
-impl<T, const N: usize> Queue<T, N> {
+impl<T, const N: usize, L: Length> Queue<T, N, L> {
     /// Panics if N is not a power of 2 or is 0
     pub const fn new() -> Self {
         assert!(N > 0, "Queue size must be greater than 0");
         assert!(N.is_power_of_two(), "Queue size must be a power of 2");
+        let () = LengthFits::<L, N>::CHECK;
 
         Self {
             data: unsafe { MaybeUninit::uninit().assume_init() },
-            head: AtomicUsize::new(0),
-            tail: AtomicUsize::new(0),
+            // all zeroes is an atomic holding 0
+            head: unsafe { mem::zeroed() },
+            tail: unsafe { mem::zeroed() },
         }
     }
 
@@ -59,12 +66,12 @@ impl<T, const N: usize> Queue<T, N> {
         ((head + 1) & (N - 1)) == tail
     }
 
-    pub fn split(&self) -> (Producer<'_, T, N>, Consumer<'_, T, N>) {
+    pub fn split(&self) -> (Producer<'_, T, N, L>, Consumer<'_, T, N, L>) {
         (Producer { queue: self }, Consumer { queue: self })
     }
 }
 
-impl<'a, T, const N: usize> Producer<'a, T, N> {
+impl<'a, T, const N: usize, L: Length> Producer<'a, T, N, L> {
     /// Pushes an item to the queue, returning an error if full
     pub fn push(&mut self, item: T) -> Result<(), QueueError> {
         let head = self.queue.head.load(Ordering::Relaxed);
@@ -106,13 +113,18 @@ impl<'a, T, const N: usize> Producer<'a, T, N> {
         self.queue.is_full()
     }
 
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+
     #[inline]
     pub fn len(&self) -> usize {
         self.queue.len()
     }
 }
 
-impl<'a, T, const N: usize> Consumer<'a, T, N> {
+impl<'a, T, const N: usize, L: Length> Consumer<'a, T, N, L> {
     /// Pops an item from the queue, returning an error if empty
     pub fn pop(&mut self) -> Result<T, QueueError> {
         let tail = self.queue.tail.load(Ordering::Relaxed);
@@ -143,13 +155,19 @@ impl<'a, T, const N: usize> Consumer<'a, T, N> {
     }
 }
 
-unsafe impl<T: Send, const N: usize> Send for Queue<T, N> {}
-unsafe impl<T: Send, const N: usize> Sync for Queue<T, N> {}
+impl<T, const N: usize, L: Length> Default for Queue<T, N, L> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
-unsafe impl<T: Send, const N: usize> Send for Producer<'_, T, N> {}
-unsafe impl<T: Send, const N: usize> Send for Consumer<'_, T, N> {}
+unsafe impl<T: Send, const N: usize, L: Length> Send for Queue<T, N, L> {}
+unsafe impl<T: Send, const N: usize, L: Length> Sync for Queue<T, N, L> {}
 
-impl<T, const N: usize> Drop for Queue<T, N> {
+unsafe impl<T: Send, const N: usize, L: Length> Send for Producer<'_, T, N, L> {}
+unsafe impl<T: Send, const N: usize, L: Length> Send for Consumer<'_, T, N, L> {}
+
+impl<T, const N: usize, L: Length> Drop for Queue<T, N, L> {
     fn drop(&mut self) {
         let mut consumer = Consumer { queue: self };
         while consumer.pop().is_ok() {}
@@ -295,4 +313,129 @@ mod tests {
             assert_eq!(value, i as i32);
         }
     }
+
+    #[test]
+    fn test_concurrent_map_insert() {
+        use std::sync::Arc;
+        use std::thread;
+        use std::vec::Vec;
+
+        use crate::ConcurrentHashMap;
+
+        let map = Arc::new(ConcurrentHashMap::<u64, u64, 4096>::new());
+
+        let handles: Vec<_> = (0..4)
+            .map(|t| {
+                let map = map.clone();
+                thread::spawn(move || {
+                    for i in 0..500 {
+                        let key = t * 1000 + i;
+                        map.insert(key, key * 2).unwrap();
+                    }
+                })
+            })
+            .collect();
+
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        assert_eq!(map.len(), 2000);
+        for t in 0..4 {
+            for i in 0..500 {
+                let key = t * 1000 + i;
+                assert_eq!(map.get(key), Some(key * 2));
+            }
+        }
+    }
+
+    #[test]
+    fn test_concurrent_map_claims() {
+        use std::sync::Arc;
+        use std::thread;
+        use std::vec::Vec;
+
+        use crate::ConcurrentHashMap;
+
+        let map = Arc::new(ConcurrentHashMap::<u32, u32, 256>::new());
+
+        // every thread races to create the same keys, each must only be claimed once
+        let handles: Vec<_> = (0..4)
+            .map(|t| {
+                let map = map.clone();
+                thread::spawn(move || {
+                    for key in 0..150 {
+                        map.insert(key, t).unwrap();
+                    }
+                })
+            })
+            .collect();
+
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        assert_eq!(map.len(), 150);
+        for key in 0..150 {
+            assert!(map.get(key).unwrap() < 4);
+        }
+    }
+
+    #[test]
+    fn test_concurrent_map_counters() {
+        use std::sync::Arc;
+        use std::thread;
+        use std::vec::Vec;
+
+        use crate::ConcurrentHashMap;
+
+        let map = Arc::new(ConcurrentHashMap::<u32, u32, 64>::new());
+        for key in 0..16 {
+            map.insert(key, 0).unwrap();
+        }
+
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                let map = map.clone();
+                thread::spawn(move || {
+                    for round in 0..1000 {
+                        let key = round % 16;
+                        loop {
+                            let current = map.get(key).unwrap();
+                            if map.compare_exchange(key, current, current + 1).is_ok() {
+                                break;
+                            }
+                        }
+                    }
+                })
+            })
+            .collect();
+
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        let total: u32 = (0..16).map(|key| map.get(key).unwrap()).sum();
+        assert_eq!(total, 4000);
+    }
+
+    #[test]
+    fn test_length_type() {
+        let queue = Queue::<u32, 128, u8>::new();
+        let (mut producer, mut consumer) = queue.split();
+
+        for cycle in 0..4 {
+            for i in 0..127 {
+                producer.push(cycle * 1000 + i).unwrap();
+            }
+            assert!(queue.is_full());
+            assert_eq!(queue.len(), 127);
+            assert_eq!(producer.push(0), Err(QueueError::Full));
+
+            for i in 0..127 {
+                assert_eq!(consumer.pop(), Ok(cycle * 1000 + i));
+            }
+            assert!(queue.is_empty());
+        }
+    }
 }
@@ -1,25 +1,32 @@
-use core::mem::MaybeUninit;
+use core::mem::{self, MaybeUninit};
 use core::ptr;
-use core::sync::atomic::{AtomicUsize, Ordering};
+use core::sync::atomic::Ordering;
+
+use crate::fixedvec::sealed::AtomicLength;
+use crate::fixedvec::{Length, LengthFits};
 
 /// Classic ring buffer implementation.
+///
+/// The head and tail indices are stored as atomics of `L`, see [`Length`].
 #[repr(align(64))]
-pub struct RingBuffer<T, const N: usize> {
+pub struct RingBuffer<T, const N: usize, L: Length = usize> {
     data: [MaybeUninit<T>; N],
-    head: AtomicUsize,
-    tail: AtomicUsize,
+    head: L::Atomic,
+    tail: L::Atomic,
 }
 
-impl<T, const N: usize> RingBuffer<T, N> {
+impl<T, const N: usize, L: Length> RingBuffer<T, N, L> {
     /// Panics if N is not a power of 2 or is 0
     pub const fn new() -> Self {
         assert!(N > 0, "Ring buffer size must be greater than 0");
         assert!(N.is_power_of_two(), "Ring buffer size must be a power of 2");
+        let () = LengthFits::<L, N>::CHECK;
 
         Self {
             data: unsafe { MaybeUninit::uninit().assume_init() },
-            head: AtomicUsize::new(0),
-            tail: AtomicUsize::new(0),
+            // all zeroes is an atomic holding 0
+            head: unsafe { mem::zeroed() },
+            tail: unsafe { mem::zeroed() },
         }
     }
 
@@ -92,10 +99,16 @@ impl<T, const N: usize> RingBuffer<T, N> {
     }
 }
 
-unsafe impl<T: Send, const N: usize> Send for RingBuffer<T, N> {}
-unsafe impl<T: Send, const N: usize> Sync for RingBuffer<T, N> {}
+impl<T, const N: usize, L: Length> Default for RingBuffer<T, N, L> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+unsafe impl<T: Send, const N: usize, L: Length> Send for RingBuffer<T, N, L> {}
+unsafe impl<T: Send, const N: usize, L: Length> Sync for RingBuffer<T, N, L> {}
 
-impl<T, const N: usize> Drop for RingBuffer<T, N> {
+impl<T, const N: usize, L: Length> Drop for RingBuffer<T, N, L> {
     fn drop(&mut self) {
         while self.pop().is_ok() {}
     }
@@ -165,4 +178,21 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_length_type() {
+        let buffer = RingBuffer::<u32, 256, u16>::new();
+        for cycle in 0..4 {
+            for i in 0..255 {
+                buffer.push(cycle * 1000 + i).unwrap();
+            }
+            assert!(buffer.is_full());
+            assert_eq!(buffer.len(), 255);
+
+            for i in 0..255 {
+                assert_eq!(buffer.pop(), Ok(cycle * 1000 + i));
+            }
+            assert!(buffer.is_empty());
+        }
+    }
 }
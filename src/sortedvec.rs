@@ -0,0 +1,452 @@
+use core::cmp::Ordering;
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::iter::{FusedIterator, Peekable};
+use core::mem;
+use core::ops::{Bound, RangeBounds};
+use core::slice;
+
+use crate::fixedvec::{FixedVec, FixedVecIntoIter};
+
+/// Fixed capacity set kept as a sorted [`FixedVec`].
+///
+/// Lookups are a binary search and inserts shift the elements after the new one, which
+/// for a few dozen elements beats hashing or walking tree nodes, in less memory than
+/// either. Iteration is in ascending order.
+pub struct SortedVec<T, const N: usize> {
+    items: FixedVec<T, N>,
+}
+
+impl<T, const N: usize> SortedVec<T, N>
+where
+    T: Ord,
+{
+    pub const fn new() -> Self {
+        Self {
+            items: FixedVec::new(),
+        }
+    }
+
+    #[inline]
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    #[inline]
+    pub const fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    #[inline]
+    pub const fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    #[inline]
+    pub const fn is_full(&self) -> bool {
+        self.items.is_full()
+    }
+
+    /// Builds a set from an iterator, returning an error if it doesn't fit
+    pub fn try_from_iter<I>(iter: I) -> Result<Self, SortedVecError>
+    where
+        I: IntoIterator<Item = T>,
+    {
+        let mut set = Self::new();
+        set.try_extend(iter)?;
+        Ok(set)
+    }
+
+    /// Adds a value to the set, returning whether it wasn't already present.
+    ///
+    /// The stored value is kept when an equal one is inserted.
+    pub fn insert(&mut self, value: T) -> Result<bool, SortedVecError> {
+        match self.search(&value) {
+            Ok(_) => Ok(false),
+            Err(index) => {
+                self.items
+                    .insert(index, value)
+                    .map_err(|_| SortedVecError::Full)?;
+                Ok(true)
+            }
+        }
+    }
+
+    /// Adds a value to the set, replacing and returning an equal value if one was present
+    pub fn replace(&mut self, value: T) -> Result<Option<T>, SortedVecError> {
+        match self.search(&value) {
+            Ok(index) => Ok(Some(mem::replace(&mut self.items[index], value))),
+            Err(index) => {
+                self.items
+                    .insert(index, value)
+                    .map_err(|_| SortedVecError::Full)?;
+                Ok(None)
+            }
+        }
+    }
+
+    pub fn contains(&self, value: &T) -> bool {
+        self.search(value).is_ok()
+    }
+
+    /// Returns a reference to the stored value equal to `value`
+    pub fn get(&self, value: &T) -> Option<&T> {
+        self.search(value).ok().map(|index| &self.items[index])
+    }
+
+    /// Removes a value from the set, returning whether it was present
+    pub fn remove(&mut self, value: &T) -> bool {
+        self.take(value).is_some()
+    }
+
+    /// Removes and returns the stored value equal to `value`
+    pub fn take(&mut self, value: &T) -> Option<T> {
+        let index = self.search(value).ok()?;
+        self.items.remove(index)
+    }
+
+    /// Smallest value, `None` if empty
+    pub fn first(&self) -> Option<&T> {
+        self.items.first()
+    }
+
+    /// Largest value, `None` if empty
+    pub fn last(&self) -> Option<&T> {
+        self.items.last()
+    }
+
+    /// Values within `range`, in ascending order
+    pub fn range<R>(&self, range: R) -> SortedVecIter<'_, T>
+    where
+        R: RangeBounds<T>,
+    {
+        let start = match range.start_bound() {
+            Bound::Included(start) => self.items.partition_point(|item| item < start),
+            Bound::Excluded(start) => self.items.partition_point(|item| item <= start),
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(end) => self.items.partition_point(|item| item <= end),
+            Bound::Excluded(end) => self.items.partition_point(|item| item < end),
+            Bound::Unbounded => self.len(),
+        };
+
+        SortedVecIter {
+            inner: self.items[start..end.max(start)].iter(),
+        }
+    }
+
+    /// Keeps only the values for which `f` returns `true`
+    pub fn retain<F>(&mut self, f: F)
+    where
+        F: FnMut(&T) -> bool,
+    {
+        self.items.retain(f);
+    }
+
+    /// Moves every value of `other` into the set in a single linear pass, keeping the
+    /// value from `other` where both have an equal one.
+    ///
+    /// Hands `other` back, with the set unchanged, if the union doesn't fit
+    pub fn merge<const M: usize>(&mut self, other: SortedVec<T, M>) -> Result<(), SortedVec<T, M>> {
+        if union_len(&self.items, &other.items, T::cmp) > N {
+            return Err(other);
+        }
+
+        let mine = mem::take(&mut self.items);
+        for value in MergeSorted::new(mine.into_iter(), other.items.into_iter(), T::cmp) {
+            let pushed = self.items.push(value);
+            debug_assert!(pushed.is_ok());
+        }
+        Ok(())
+    }
+
+    pub fn clear(&mut self) {
+        self.items.clear();
+    }
+
+    /// Extends the set with the contents of an iterator, stopping at the first value that doesn't fit
+    pub fn try_extend<I>(&mut self, iter: I) -> Result<(), SortedVecError>
+    where
+        I: IntoIterator<Item = T>,
+    {
+        for value in iter {
+            self.insert(value)?;
+        }
+        Ok(())
+    }
+
+    fn search(&self, value: &T) -> Result<usize, usize> {
+        self.items.binary_search(value)
+    }
+}
+
+impl<T, const N: usize> SortedVec<T, N> {
+    /// Values in ascending order
+    pub fn iter(&self) -> SortedVecIter<'_, T> {
+        SortedVecIter {
+            inner: self.items.as_slice().iter(),
+        }
+    }
+
+    /// The values as a sorted slice
+    pub fn as_slice(&self) -> &[T] {
+        self.items.as_slice()
+    }
+}
+
+/// Number of distinct elements across two sorted slices
+pub(crate) fn union_len<A, B, F>(a: &[A], b: &[B], mut cmp: F) -> usize
+where
+    F: FnMut(&A, &B) -> Ordering,
+{
+    let (mut i, mut j, mut len) = (0, 0, 0);
+    while i < a.len() && j < b.len() {
+        match cmp(&a[i], &b[j]) {
+            Ordering::Less => i += 1,
+            Ordering::Greater => j += 1,
+            Ordering::Equal => {
+                i += 1;
+                j += 1;
+            }
+        }
+        len += 1;
+    }
+    len + (a.len() - i) + (b.len() - j)
+}
+
+/// Merges two sorted iterators into one, taking the item from `b` where both have an
+/// equal one
+pub(crate) struct MergeSorted<T, A, B, F>
+where
+    A: Iterator<Item = T>,
+    B: Iterator<Item = T>,
+{
+    a: Peekable<A>,
+    b: Peekable<B>,
+    cmp: F,
+}
+
+impl<T, A, B, F> MergeSorted<T, A, B, F>
+where
+    A: Iterator<Item = T>,
+    B: Iterator<Item = T>,
+    F: FnMut(&T, &T) -> Ordering,
+{
+    pub(crate) fn new(a: A, b: B, cmp: F) -> Self {
+        Self {
+            a: a.peekable(),
+            b: b.peekable(),
+            cmp,
+        }
+    }
+}
+
+impl<T, A, B, F> Iterator for MergeSorted<T, A, B, F>
+where
+    A: Iterator<Item = T>,
+    B: Iterator<Item = T>,
+    F: FnMut(&T, &T) -> Ordering,
+{
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let order = match (self.a.peek(), self.b.peek()) {
+            (Some(a), Some(b)) => (self.cmp)(a, b),
+            (Some(_), None) => Ordering::Less,
+            (None, _) => Ordering::Greater,
+        };
+
+        match order {
+            Ordering::Less => self.a.next(),
+            Ordering::Greater => self.b.next(),
+            Ordering::Equal => {
+                self.a.next();
+                self.b.next()
+            }
+        }
+    }
+}
+
+impl<T, const N: usize> Default for SortedVec<T, N>
+where
+    T: Ord,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Clone, const N: usize> Clone for SortedVec<T, N> {
+    fn clone(&self) -> Self {
+        Self {
+            items: self.items.clone(),
+        }
+    }
+}
+
+impl<T: fmt::Debug, const N: usize> fmt::Debug for SortedVec<T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+impl<T: PartialEq, const N: usize, const M: usize> PartialEq<SortedVec<T, M>> for SortedVec<T, N> {
+    fn eq(&self, other: &SortedVec<T, M>) -> bool {
+        self.as_slice() == other.as_slice()
+    }
+}
+
+impl<T: Eq, const N: usize> Eq for SortedVec<T, N> {}
+
+impl<T: Hash, const N: usize> Hash for SortedVec<T, N> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_slice().hash(state);
+    }
+}
+
+/// Panics if the iterator yields more values than the set can hold,
+/// use [`SortedVec::try_from_iter`] to handle that case.
+impl<T, const N: usize> FromIterator<T> for SortedVec<T, N>
+where
+    T: Ord,
+{
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        Self::try_from_iter(iter).expect("SortedVec capacity exceeded")
+    }
+}
+
+/// Panics if the set runs out of room, use [`SortedVec::try_extend`] to handle that case.
+impl<T, const N: usize> Extend<T> for SortedVec<T, N>
+where
+    T: Ord,
+{
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        self.try_extend(iter).expect("SortedVec capacity exceeded");
+    }
+}
+
+impl<T, const N: usize> IntoIterator for SortedVec<T, N> {
+    type Item = T;
+    type IntoIter = FixedVecIntoIter<T, N>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.items.into_iter()
+    }
+}
+
+impl<'a, T, const N: usize> IntoIterator for &'a SortedVec<T, N> {
+    type Item = &'a T;
+    type IntoIter = SortedVecIter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Iterator over values in ascending order, see [`SortedVec::iter`] and
+/// [`SortedVec::range`]
+pub struct SortedVecIter<'a, T> {
+    inner: slice::Iter<'a, T>,
+}
+
+impl<'a, T> Iterator for SortedVecIter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<'a, T> DoubleEndedIterator for SortedVecIter<'a, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back()
+    }
+}
+
+impl<'a, T> ExactSizeIterator for SortedVecIter<'a, T> {
+    fn len(&self) -> usize {
+        self.inner.len()
+    }
+}
+
+impl<'a, T> FusedIterator for SortedVecIter<'a, T> {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortedVecError {
+    Full,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::vec::Vec;
+
+    #[test]
+    fn test_insert_sorted() {
+        let mut set = SortedVec::<u32, 8>::new();
+        for value in [5, 1, 4, 1, 3] {
+            set.insert(value).unwrap();
+        }
+
+        assert_eq!(set.as_slice(), [1, 3, 4, 5]);
+        assert_eq!(set.insert(4), Ok(false));
+        assert!(set.contains(&3));
+        assert!(!set.contains(&2));
+        assert_eq!(set.first(), Some(&1));
+        assert_eq!(set.last(), Some(&5));
+
+        assert!(set.remove(&4));
+        assert!(!set.remove(&4));
+        assert_eq!(set.take(&1), Some(1));
+        assert_eq!(set.as_slice(), [3, 5]);
+    }
+
+    #[test]
+    fn test_full() {
+        let mut set = SortedVec::<u32, 2>::try_from_iter([2, 1]).unwrap();
+        assert_eq!(set.insert(3), Err(SortedVecError::Full));
+        assert_eq!(set.insert(2), Ok(false));
+        assert_eq!(set.replace(2), Ok(Some(2)));
+        assert!(SortedVec::<u32, 2>::try_from_iter([1, 2, 3]).is_err());
+    }
+
+    #[test]
+    fn test_range() {
+        let set: SortedVec<u32, 16> = (0..10).map(|i| i * 10).collect();
+        let collect = |iter: SortedVecIter<'_, u32>| iter.copied().collect::<Vec<_>>();
+
+        assert_eq!(collect(set.range(20..50)), [20, 30, 40]);
+        assert_eq!(collect(set.range(15..=50)), [20, 30, 40, 50]);
+        assert_eq!(collect(set.range(..15)), [0, 10]);
+        assert_eq!(collect(set.range(85..)), [90]);
+        assert_eq!(collect(set.range(41..45)), []);
+        assert_eq!(
+            collect(set.range((Bound::Excluded(70), Bound::Unbounded))),
+            [80, 90]
+        );
+        assert_eq!(set.range(..).len(), 10);
+    }
+
+    #[test]
+    fn test_merge() {
+        let mut a = SortedVec::<u32, 8>::try_from_iter([1, 4, 6]).unwrap();
+        let b = SortedVec::<u32, 4>::try_from_iter([2, 4, 7, 9]).unwrap();
+
+        a.merge(b).unwrap();
+        assert_eq!(a.as_slice(), [1, 2, 4, 6, 7, 9]);
+
+        let c = SortedVec::<u32, 4>::try_from_iter([0, 3, 5, 8]).unwrap();
+        let c = a.merge(c).unwrap_err();
+        assert_eq!(a.len(), 6);
+        assert_eq!(c.len(), 4);
+
+        let mut empty = SortedVec::<u32, 4>::new();
+        empty.merge(c).unwrap();
+        assert_eq!(empty.as_slice(), [0, 3, 5, 8]);
+    }
+}
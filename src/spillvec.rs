@@ -0,0 +1,773 @@
+use alloc::vec::{self, Vec};
+use core::borrow::{Borrow, BorrowMut};
+use core::cmp::Ordering;
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::iter::FusedIterator;
+use core::mem;
+use core::ops::{Deref, DerefMut, Index, IndexMut, RangeBounds};
+use core::slice::{self, SliceIndex};
+
+use crate::fixedvec::{FixedVec, FixedVecDrain, FixedVecError, FixedVecIntoIter, bounds};
+
+/// Vector that keeps up to `N` elements inline in a [`FixedVec`] and moves them to a
+/// heap `Vec` once it outgrows that.
+///
+/// The API mirrors [`FixedVec`], except that methods which can only fail for lack of
+/// room (`push`, `resize`, `extend_from_slice`, ...) spill to the heap instead and
+/// return nothing. Index errors are still reported. Moving back inline only happens
+/// through [`shrink_to_inline`](Self::shrink_to_inline).
+pub struct SpillVec<T, const N: usize> {
+    storage: Storage<T, N>,
+}
+
+enum Storage<T, const N: usize> {
+    Inline(FixedVec<T, N>),
+    Heap(Vec<T>),
+}
+
+impl<T, const N: usize> SpillVec<T, N> {
+    pub const fn new() -> Self {
+        Self {
+            storage: Storage::Inline(FixedVec::new()),
+        }
+    }
+
+    /// Number of elements the vector holds without allocating (again)
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        match &self.storage {
+            Storage::Inline(_) => N,
+            Storage::Heap(heap) => heap.capacity(),
+        }
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        match &self.storage {
+            Storage::Inline(inline) => inline.len(),
+            Storage::Heap(heap) => heap.len(),
+        }
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Whether the elements have moved to the heap
+    #[inline]
+    pub const fn spilled(&self) -> bool {
+        matches!(self.storage, Storage::Heap(_))
+    }
+
+    /// Moves the elements back inline and frees the heap buffer, if they fit.
+    ///
+    /// Returns whether the elements are inline afterwards
+    pub fn shrink_to_inline(&mut self) -> bool {
+        if let Storage::Heap(heap) = &mut self.storage {
+            if heap.len() > N {
+                return false;
+            }
+
+            let Ok(inline) = FixedVec::try_from(mem::take(heap)) else {
+                unreachable!()
+            };
+            self.storage = Storage::Inline(inline);
+        }
+        true
+    }
+
+    /// Makes room for at least `additional` more elements, spilling to the heap if
+    /// they don't fit inline
+    pub fn reserve(&mut self, additional: usize) {
+        match &mut self.storage {
+            Storage::Inline(inline) if additional > inline.remaining_capacity() => {
+                self.spill(additional);
+            }
+            Storage::Inline(_) => {}
+            Storage::Heap(heap) => heap.reserve(additional),
+        }
+    }
+
+    /// Pushes an element to the end of the vector, spilling to the heap if full
+    pub fn push(&mut self, value: T) {
+        match &mut self.storage {
+            Storage::Inline(inline) => {
+                if let Err(value) = inline.try_push(value) {
+                    self.spill(1).push(value);
+                }
+            }
+            Storage::Heap(heap) => heap.push(value),
+        }
+    }
+
+    /// Removes and returns the last element, returning `None` if empty
+    pub fn pop(&mut self) -> Option<T> {
+        match &mut self.storage {
+            Storage::Inline(inline) => inline.pop(),
+            Storage::Heap(heap) => heap.pop(),
+        }
+    }
+
+    /// Inserts an element at the given index, shifting all elements after the index to the right
+    /// and returning an error if the index is invalid
+    pub fn insert(&mut self, index: usize, value: T) -> Result<(), FixedVecError> {
+        if index > self.len() {
+            return Err(FixedVecError::IndexOutOfBounds);
+        }
+
+        self.reserve(1);
+        match &mut self.storage {
+            Storage::Inline(inline) => inline.insert(index, value),
+            Storage::Heap(heap) => {
+                heap.insert(index, value);
+                Ok(())
+            }
+        }
+    }
+
+    /// Removes and returns the element at the given index,
+    /// shifting all elements after the index to the left and
+    /// returning `None` if the index is out of bounds
+    pub fn remove(&mut self, index: usize) -> Option<T> {
+        match &mut self.storage {
+            Storage::Inline(inline) => inline.remove(index),
+            Storage::Heap(heap) if index < heap.len() => Some(heap.remove(index)),
+            Storage::Heap(_) => None,
+        }
+    }
+
+    /// Removes and returns the element at the given index, replacing it with the last
+    /// element, returning `None` if the index is out of bounds
+    pub fn swap_remove(&mut self, index: usize) -> Option<T> {
+        match &mut self.storage {
+            Storage::Inline(inline) => inline.swap_remove(index),
+            Storage::Heap(heap) if index < heap.len() => Some(heap.swap_remove(index)),
+            Storage::Heap(_) => None,
+        }
+    }
+
+    /// Removes every element, keeping the heap buffer if there is one
+    pub fn clear(&mut self) {
+        match &mut self.storage {
+            Storage::Inline(inline) => inline.clear(),
+            Storage::Heap(heap) => heap.clear(),
+        }
+    }
+
+    /// Truncates the vector to the specified length
+    ///
+    /// If current length is greater than `len`, the vec is truncated
+    /// to exactly `len` elements
+    pub fn truncate(&mut self, len: usize) {
+        match &mut self.storage {
+            Storage::Inline(inline) => inline.truncate(len),
+            Storage::Heap(heap) => heap.truncate(len),
+        }
+    }
+
+    /// Splits the vector in two at the given index, returning everything from `at`
+    /// onwards, panicking if `at` is out of bounds.
+    ///
+    /// The returned part is inline if it fits.
+    pub fn split_off(&mut self, at: usize) -> Self {
+        assert!(at <= self.len(), "Index {} out of bounds", at);
+
+        match &mut self.storage {
+            Storage::Inline(inline) => Self {
+                storage: Storage::Inline(inline.split_off(at)),
+            },
+            Storage::Heap(heap) => {
+                let mut other = Self {
+                    storage: Storage::Heap(heap.split_off(at)),
+                };
+                other.shrink_to_inline();
+                other
+            }
+        }
+    }
+
+    /// Resizes the vector in place, filling new slots with the results of `f`
+    pub fn resize_with<F>(&mut self, new_len: usize, f: F)
+    where
+        F: FnMut() -> T,
+    {
+        self.reserve(new_len.saturating_sub(self.len()));
+        match &mut self.storage {
+            Storage::Inline(inline) => {
+                let resized = inline.resize_with(new_len, f);
+                debug_assert!(resized.is_ok());
+            }
+            Storage::Heap(heap) => heap.resize_with(new_len, f),
+        }
+    }
+
+    /// Keeps only the elements for which `f` returns `true`, in their original order
+    pub fn retain<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&T) -> bool,
+    {
+        self.retain_mut(|item| f(item));
+    }
+
+    /// Like [`retain`](Self::retain), but with mutable access to each element
+    pub fn retain_mut<F>(&mut self, f: F)
+    where
+        F: FnMut(&mut T) -> bool,
+    {
+        match &mut self.storage {
+            Storage::Inline(inline) => inline.retain_mut(f),
+            Storage::Heap(heap) => heap.retain_mut(f),
+        }
+    }
+
+    /// Removes consecutive elements that map to the same key
+    pub fn dedup_by_key<F, K>(&mut self, mut key: F)
+    where
+        F: FnMut(&mut T) -> K,
+        K: PartialEq,
+    {
+        self.dedup_by(|a, b| key(a) == key(b));
+    }
+
+    /// Removes consecutive elements for which `same_bucket(current, previous)` returns
+    /// `true`, keeping the first of each run
+    pub fn dedup_by<F>(&mut self, same_bucket: F)
+    where
+        F: FnMut(&mut T, &mut T) -> bool,
+    {
+        match &mut self.storage {
+            Storage::Inline(inline) => inline.dedup_by(same_bucket),
+            Storage::Heap(heap) => heap.dedup_by(same_bucket),
+        }
+    }
+
+    /// Removes the elements in `range` and returns them as an iterator, panicking if
+    /// the range is out of bounds
+    pub fn drain<R>(&mut self, range: R) -> SpillVecDrain<'_, T, N>
+    where
+        R: RangeBounds<usize>,
+    {
+        let inner = match &mut self.storage {
+            Storage::Inline(inline) => Drain::Inline(inline.drain(range)),
+            Storage::Heap(heap) => Drain::Heap(heap.drain(range)),
+        };
+        SpillVecDrain { inner }
+    }
+
+    /// Replaces the elements in `range` with the items of `replace_with`, returning the
+    /// removed elements. Panics if the range is out of bounds.
+    ///
+    /// Unlike [`FixedVec::splice`] this can't run out of room, so it returns the
+    /// removed elements directly.
+    pub fn splice<R, I>(&mut self, range: R, replace_with: I) -> Self
+    where
+        R: RangeBounds<usize>,
+        I: IntoIterator<Item = T>,
+    {
+        let (start, end) = bounds(range, self.len());
+        let tail = self.split_off(end);
+        let removed = self.split_off(start);
+
+        self.extend(replace_with);
+        self.extend(tail);
+        removed
+    }
+
+    pub fn iter(&self) -> slice::Iter<'_, T> {
+        self.as_slice().iter()
+    }
+
+    pub fn iter_mut(&mut self) -> slice::IterMut<'_, T> {
+        self.as_mut_slice().iter_mut()
+    }
+
+    pub fn as_slice(&self) -> &[T] {
+        match &self.storage {
+            Storage::Inline(inline) => inline.as_slice(),
+            Storage::Heap(heap) => heap.as_slice(),
+        }
+    }
+
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        match &mut self.storage {
+            Storage::Inline(inline) => inline.as_mut_slice(),
+            Storage::Heap(heap) => heap.as_mut_slice(),
+        }
+    }
+
+    /// Clones and appends every element of `other`, spilling to the heap if needed
+    pub fn extend_from_slice(&mut self, other: &[T])
+    where
+        T: Clone,
+    {
+        self.reserve(other.len());
+        match &mut self.storage {
+            Storage::Inline(inline) => {
+                let extended = inline.extend_from_slice(other);
+                debug_assert!(extended.is_ok());
+            }
+            Storage::Heap(heap) => heap.extend_from_slice(other),
+        }
+    }
+
+    /// Resizes the vector in place, filling new slots with clones of `value`
+    pub fn resize(&mut self, new_len: usize, value: T)
+    where
+        T: Clone,
+    {
+        self.resize_with(new_len, || value.clone());
+    }
+
+    /// Removes consecutive repeated elements
+    pub fn dedup(&mut self)
+    where
+        T: PartialEq,
+    {
+        self.dedup_by(|a, b| a == b);
+    }
+
+    /// Converts into a `Vec`, reusing the heap buffer if there is one
+    pub fn into_vec(self) -> Vec<T> {
+        match self.storage {
+            Storage::Inline(inline) => inline.into_iter().collect(),
+            Storage::Heap(heap) => heap,
+        }
+    }
+
+    /// Moves the inline elements into a heap `Vec` with room for `additional` more,
+    /// returning it
+    fn spill(&mut self, additional: usize) -> &mut Vec<T> {
+        if let Storage::Inline(inline) = &mut self.storage {
+            let mut heap = Vec::with_capacity((inline.len() + additional).max(N * 2));
+            heap.extend(inline.drain(..));
+            self.storage = Storage::Heap(heap);
+        }
+
+        match &mut self.storage {
+            Storage::Heap(heap) => heap,
+            Storage::Inline(_) => unreachable!(),
+        }
+    }
+}
+
+impl<T, const N: usize> Default for SpillVec<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, const N: usize> Deref for SpillVec<T, N> {
+    type Target = [T];
+
+    fn deref(&self) -> &Self::Target {
+        self.as_slice()
+    }
+}
+
+impl<T, const N: usize> DerefMut for SpillVec<T, N> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.as_mut_slice()
+    }
+}
+
+impl<T, const N: usize> AsRef<[T]> for SpillVec<T, N> {
+    fn as_ref(&self) -> &[T] {
+        self.as_slice()
+    }
+}
+
+impl<T, const N: usize> AsMut<[T]> for SpillVec<T, N> {
+    fn as_mut(&mut self) -> &mut [T] {
+        self.as_mut_slice()
+    }
+}
+
+impl<T, const N: usize> Borrow<[T]> for SpillVec<T, N> {
+    fn borrow(&self) -> &[T] {
+        self.as_slice()
+    }
+}
+
+impl<T, const N: usize> BorrowMut<[T]> for SpillVec<T, N> {
+    fn borrow_mut(&mut self) -> &mut [T] {
+        self.as_mut_slice()
+    }
+}
+
+impl<T, I, const N: usize> Index<I> for SpillVec<T, N>
+where
+    I: SliceIndex<[T]>,
+{
+    type Output = I::Output;
+
+    fn index(&self, index: I) -> &Self::Output {
+        &self.as_slice()[index]
+    }
+}
+
+impl<T, I, const N: usize> IndexMut<I> for SpillVec<T, N>
+where
+    I: SliceIndex<[T]>,
+{
+    fn index_mut(&mut self, index: I) -> &mut Self::Output {
+        &mut self.as_mut_slice()[index]
+    }
+}
+
+impl<'a, T, const N: usize> IntoIterator for &'a SpillVec<T, N> {
+    type Item = &'a T;
+    type IntoIter = slice::Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, T, const N: usize> IntoIterator for &'a mut SpillVec<T, N> {
+    type Item = &'a mut T;
+    type IntoIter = slice::IterMut<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+impl<T, const N: usize> IntoIterator for SpillVec<T, N> {
+    type Item = T;
+    type IntoIter = SpillVecIntoIter<T, N>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        let inner = match self.storage {
+            Storage::Inline(inline) => IntoIter::Inline(inline.into_iter()),
+            Storage::Heap(heap) => IntoIter::Heap(heap.into_iter()),
+        };
+        SpillVecIntoIter { inner }
+    }
+}
+
+impl<T: Clone, const N: usize> Clone for SpillVec<T, N> {
+    fn clone(&self) -> Self {
+        let storage = match &self.storage {
+            Storage::Inline(inline) => Storage::Inline(inline.clone()),
+            Storage::Heap(heap) => Storage::Heap(heap.clone()),
+        };
+        Self { storage }
+    }
+}
+
+impl<T: fmt::Debug, const N: usize> fmt::Debug for SpillVec<T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T, U, const N: usize, const M: usize> PartialEq<SpillVec<U, M>> for SpillVec<T, N>
+where
+    T: PartialEq<U>,
+{
+    fn eq(&self, other: &SpillVec<U, M>) -> bool {
+        self.as_slice() == other.as_slice()
+    }
+}
+
+impl<T, U, const N: usize> PartialEq<[U]> for SpillVec<T, N>
+where
+    T: PartialEq<U>,
+{
+    fn eq(&self, other: &[U]) -> bool {
+        self.as_slice() == other
+    }
+}
+
+impl<T, U, const N: usize> PartialEq<&[U]> for SpillVec<T, N>
+where
+    T: PartialEq<U>,
+{
+    fn eq(&self, other: &&[U]) -> bool {
+        self.as_slice() == *other
+    }
+}
+
+impl<T, U, const N: usize, const M: usize> PartialEq<[U; M]> for SpillVec<T, N>
+where
+    T: PartialEq<U>,
+{
+    fn eq(&self, other: &[U; M]) -> bool {
+        self.as_slice() == other
+    }
+}
+
+impl<T: Eq, const N: usize> Eq for SpillVec<T, N> {}
+
+impl<T: PartialOrd, const N: usize, const M: usize> PartialOrd<SpillVec<T, M>> for SpillVec<T, N> {
+    fn partial_cmp(&self, other: &SpillVec<T, M>) -> Option<Ordering> {
+        self.as_slice().partial_cmp(other.as_slice())
+    }
+}
+
+impl<T: Ord, const N: usize> Ord for SpillVec<T, N> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.as_slice().cmp(other.as_slice())
+    }
+}
+
+/// Hashes the same as the slice it derefs to, as [`Borrow<[T]>`] requires
+impl<T: Hash, const N: usize> Hash for SpillVec<T, N> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_slice().hash(state);
+    }
+}
+
+/// Spills to the heap if the array is longer than `N`
+impl<T, const N: usize, const M: usize> From<[T; M]> for SpillVec<T, N> {
+    fn from(array: [T; M]) -> Self {
+        let mut vec = Self::new();
+        vec.extend(array);
+        vec
+    }
+}
+
+impl<T, const N: usize> From<FixedVec<T, N>> for SpillVec<T, N> {
+    fn from(inline: FixedVec<T, N>) -> Self {
+        Self {
+            storage: Storage::Inline(inline),
+        }
+    }
+}
+
+/// Keeps the elements in the `Vec`'s buffer, even if they would fit inline
+impl<T, const N: usize> From<Vec<T>> for SpillVec<T, N> {
+    fn from(heap: Vec<T>) -> Self {
+        Self {
+            storage: Storage::Heap(heap),
+        }
+    }
+}
+
+impl<T, const N: usize> FromIterator<T> for SpillVec<T, N> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut vec = Self::new();
+        vec.extend(iter);
+        vec
+    }
+}
+
+impl<T, const N: usize> Extend<T> for SpillVec<T, N> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        let iter = iter.into_iter();
+        self.reserve(iter.size_hint().0);
+        for item in iter {
+            self.push(item);
+        }
+    }
+}
+
+/// Draining iterator, see [`SpillVec::drain`]
+pub struct SpillVecDrain<'a, T, const N: usize> {
+    inner: Drain<'a, T, N>,
+}
+
+enum Drain<'a, T, const N: usize> {
+    Inline(FixedVecDrain<'a, T, N>),
+    Heap(vec::Drain<'a, T>),
+}
+
+impl<'a, T, const N: usize> Iterator for SpillVecDrain<'a, T, N> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match &mut self.inner {
+            Drain::Inline(inner) => inner.next(),
+            Drain::Heap(inner) => inner.next(),
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.len();
+        (len, Some(len))
+    }
+}
+
+impl<'a, T, const N: usize> DoubleEndedIterator for SpillVecDrain<'a, T, N> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        match &mut self.inner {
+            Drain::Inline(inner) => inner.next_back(),
+            Drain::Heap(inner) => inner.next_back(),
+        }
+    }
+}
+
+impl<'a, T, const N: usize> ExactSizeIterator for SpillVecDrain<'a, T, N> {
+    fn len(&self) -> usize {
+        match &self.inner {
+            Drain::Inline(inner) => inner.len(),
+            Drain::Heap(inner) => inner.len(),
+        }
+    }
+}
+
+impl<'a, T, const N: usize> FusedIterator for SpillVecDrain<'a, T, N> {}
+
+/// Owning iterator, see [`SpillVec::into_iter`]
+pub struct SpillVecIntoIter<T, const N: usize> {
+    inner: IntoIter<T, N>,
+}
+
+enum IntoIter<T, const N: usize> {
+    Inline(FixedVecIntoIter<T, N>),
+    Heap(vec::IntoIter<T>),
+}
+
+impl<T, const N: usize> Iterator for SpillVecIntoIter<T, N> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match &mut self.inner {
+            IntoIter::Inline(inner) => inner.next(),
+            IntoIter::Heap(inner) => inner.next(),
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.len();
+        (len, Some(len))
+    }
+}
+
+impl<T, const N: usize> DoubleEndedIterator for SpillVecIntoIter<T, N> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        match &mut self.inner {
+            IntoIter::Inline(inner) => inner.next_back(),
+            IntoIter::Heap(inner) => inner.next_back(),
+        }
+    }
+}
+
+impl<T, const N: usize> ExactSizeIterator for SpillVecIntoIter<T, N> {
+    fn len(&self) -> usize {
+        match &self.inner {
+            IntoIter::Inline(inner) => inner.len(),
+            IntoIter::Heap(inner) => inner.len(),
+        }
+    }
+}
+
+impl<T, const N: usize> FusedIterator for SpillVecIntoIter<T, N> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::rc::Rc;
+
+    #[test]
+    fn test_push_spills() {
+        let mut vec = SpillVec::<u32, 4>::new();
+        for i in 0..4 {
+            vec.push(i);
+        }
+        assert!(!vec.spilled());
+        assert_eq!(vec.capacity(), 4);
+
+        vec.push(4);
+        assert!(vec.spilled());
+        assert!(vec.capacity() >= 8);
+        assert_eq!(vec, [0, 1, 2, 3, 4]);
+
+        assert_eq!(vec.pop(), Some(4));
+        assert_eq!(vec.remove(0), Some(0));
+        assert_eq!(vec.remove(9), None);
+        assert_eq!(vec.insert(9, 0), Err(FixedVecError::IndexOutOfBounds));
+        assert!(vec.spilled());
+
+        assert!(vec.shrink_to_inline());
+        assert!(!vec.spilled());
+        assert_eq!(vec, [1, 2, 3]);
+    }
+
+    #[test]
+    fn test_shrink_too_long() {
+        let mut vec: SpillVec<u8, 2> = [1, 2, 3].into();
+        assert!(vec.spilled());
+        assert!(!vec.shrink_to_inline());
+
+        vec.truncate(2);
+        assert!(vec.shrink_to_inline());
+        assert!(vec.shrink_to_inline());
+        assert_eq!(vec.capacity(), 2);
+    }
+
+    #[test]
+    fn test_insert_and_resize() {
+        let mut vec = SpillVec::<i32, 3>::from([1, 3, 4]);
+        vec.insert(1, 2).unwrap();
+        assert!(vec.spilled());
+        assert_eq!(vec, [1, 2, 3, 4]);
+
+        let mut small = SpillVec::<i32, 3>::new();
+        small.resize(2, 7);
+        assert!(!small.spilled());
+        small.resize_with(5, || 0);
+        assert_eq!(small, [7, 7, 0, 0, 0]);
+
+        small.extend_from_slice(&[1, 1]);
+        small.dedup();
+        small.retain(|&x| x != 7);
+        assert_eq!(small, [0, 1]);
+    }
+
+    #[test]
+    fn test_drain_splice_split() {
+        let mut vec: SpillVec<u32, 4> = (0..8).collect();
+        let drained: std::vec::Vec<_> = vec.drain(2..5).collect();
+        assert_eq!(drained, [2, 3, 4]);
+        assert_eq!(vec, [0, 1, 5, 6, 7]);
+
+        let tail = vec.split_off(3);
+        assert!(!tail.spilled());
+        assert_eq!(tail, [6, 7]);
+
+        let mut inline = SpillVec::<u32, 4>::from([1, 2, 3]);
+        let removed = inline.splice(1..2, [20, 21, 22]);
+        assert_eq!(removed, [2]);
+        assert_eq!(inline, [1, 20, 21, 22, 3]);
+        assert!(inline.spilled());
+    }
+
+    #[test]
+    fn test_drops_and_into_iter() {
+        let counter = Rc::new(());
+        let mut vec = SpillVec::<Rc<()>, 2>::new();
+        for _ in 0..5 {
+            vec.push(counter.clone());
+        }
+        assert_eq!(Rc::strong_count(&counter), 6);
+
+        let mut iter = vec.clone().into_iter();
+        assert_eq!(iter.len(), 5);
+        iter.next();
+        drop(iter);
+        assert_eq!(Rc::strong_count(&counter), 6);
+
+        vec.truncate(1);
+        assert!(vec.shrink_to_inline());
+        assert_eq!(Rc::strong_count(&counter), 2);
+        drop(vec);
+        assert_eq!(Rc::strong_count(&counter), 1);
+    }
+
+    #[test]
+    fn test_conversions() {
+        let fixed = crate::fixedvec![1, 2];
+        let vec = SpillVec::<u8, 4>::from(fixed);
+        assert!(!vec.spilled());
+        assert_eq!(vec.clone().into_vec(), std::vec![1, 2]);
+
+        let heap = SpillVec::<u8, 4>::from(std::vec![1, 2]);
+        assert!(heap.spilled());
+        assert_eq!(heap, vec);
+        assert_eq!(std::format!("{:?}", heap), "[1, 2]");
+        assert_eq!(heap[1], 2);
+    }
+}
@@ -1,17 +1,23 @@
-use core::mem::MaybeUninit;
+use core::mem::{self, MaybeUninit};
 use core::ptr;
 
+use crate::fixedvec::{Fits, Length, LengthFits, length_from_usize, length_to_usize};
+
 /// Inline zero-allocation stack implementation.
-pub struct Stack<T, const N: usize> {
+///
+/// The length is stored as `L`, see [`Length`].
+pub struct Stack<T, const N: usize, L: Length = usize> {
     data: [MaybeUninit<T>; N],
-    len: usize,
+    len: L,
 }
 
-impl<T, const N: usize> Stack<T, N> {
+impl<T, const N: usize, L: Length> Stack<T, N, L> {
     pub const fn new() -> Self {
+        let () = LengthFits::<L, N>::CHECK;
+
         Self {
             data: unsafe { MaybeUninit::uninit().assume_init() },
-            len: 0,
+            len: length_from_usize(0),
         }
     }
 
@@ -22,63 +28,89 @@ impl<T, const N: usize> Stack<T, N> {
 
     #[inline]
     pub const fn len(&self) -> usize {
-        self.len
+        length_to_usize(&self.len)
+    }
+
+    #[inline]
+    const fn set_len(&mut self, len: usize) {
+        self.len = length_from_usize(len);
     }
 
     #[inline]
     pub const fn is_empty(&self) -> bool {
-        self.len == 0
+        self.len() == 0
     }
 
     #[inline]
     pub const fn is_full(&self) -> bool {
-        self.len == N
+        self.len() == N
     }
 
     #[inline]
     pub const fn remaining_capacity(&self) -> usize {
-        N - self.len
+        N - self.len()
+    }
+
+    /// Builds a stack from `array`, with its last item on top, usually through
+    /// [`stack!`](crate::stack).
+    ///
+    /// Fails to compile if the array is longer than the stack's capacity
+    pub const fn from_array<const M: usize>(array: [T; M]) -> Self {
+        let () = Fits::<M, N>::CHECK;
+
+        let mut stack = Self::new();
+        unsafe {
+            ptr::copy_nonoverlapping(
+                (&raw const array).cast::<T>(),
+                stack.data.as_mut_ptr().cast::<T>(),
+                M,
+            );
+        }
+        mem::forget(array);
+        stack.set_len(M);
+        stack
     }
 
     /// Pushes an item onto the stack, returning an error if full
-    pub fn push(&mut self, item: T) -> Result<(), StackError> {
-        if self.len >= N {
+    pub const fn push(&mut self, item: T) -> Result<(), StackError> {
+        if self.len() >= N {
+            crate::discard(item);
             return Err(StackError::Overflow);
         }
 
         unsafe {
-            ptr::write(self.data[self.len].as_mut_ptr(), item);
+            ptr::write(self.data[self.len()].as_mut_ptr(), item);
         }
-        self.len += 1;
+        self.set_len(self.len() + 1);
         Ok(())
     }
 
     /// Pops an item from the stack, returning an error if empty
     pub fn pop(&mut self) -> Result<T, StackError> {
-        if self.len == 0 {
+        if self.is_empty() {
             return Err(StackError::Underflow);
         }
 
-        self.len -= 1;
-        let item = unsafe { ptr::read(self.data[self.len].as_ptr()) };
+        self.set_len(self.len() - 1);
+        let item = unsafe { ptr::read(self.data[self.len()].as_ptr()) };
         Ok(item)
     }
 
     /// Returns a reference to the top item without removing it, returning `None` if empty
     pub fn peek(&self) -> Option<&T> {
-        if self.len == 0 {
+        if self.is_empty() {
             None
         } else {
-            Some(unsafe { &*self.data[self.len - 1].as_ptr() })
+            Some(unsafe { &*self.data[self.len() - 1].as_ptr() })
         }
     }
 
     /// Returns a mutable reference to the top item without removing it, returning `None` if empty
     pub fn peek_mut(&mut self) -> Option<&mut T> {
-        if self.len == 0 {
+        if self.is_empty() {
             None
         } else {
-            Some(unsafe { &mut *self.data[self.len - 1].as_mut_ptr() })
+            Some(unsafe { &mut *self.data[self.len() - 1].as_mut_ptr() })
         }
     }
 
@@ -88,36 +120,57 @@ impl<T, const N: usize> Stack<T, N> {
 
     pub fn iter(&self) -> StackIter<'_, T> {
         StackIter {
-            data: &self.data[..self.len],
-            index: self.len,
+            data: &self.data[..self.len()],
+            index: self.len(),
         }
     }
 
-    pub fn try_push(&mut self, item: T) -> Result<(), T> {
-        if self.len >= N {
+    pub const fn try_push(&mut self, item: T) -> Result<(), T> {
+        if self.len() >= N {
             return Err(item);
         }
 
         unsafe {
-            ptr::write(self.data[self.len].as_mut_ptr(), item);
+            ptr::write(self.data[self.len()].as_mut_ptr(), item);
         }
-        self.len += 1;
+        self.set_len(self.len() + 1);
         Ok(())
     }
 }
 
-impl<T, const N: usize> Default for Stack<T, N> {
+impl<T, const N: usize, L: Length> Default for Stack<T, N, L> {
     fn default() -> Self {
         Self::new()
     }
 }
 
-impl<T, const N: usize> Drop for Stack<T, N> {
+impl<T, const N: usize, L: Length> Drop for Stack<T, N, L> {
     fn drop(&mut self) {
         self.clear();
     }
 }
 
+/// Builds a [`Stack`](crate::Stack) from a list of items, the last one on top, or
+/// from one item repeated. The capacity comes from the type being built, and more
+/// items than that is a compile error.
+///
+/// ```
+/// use sakurai::{Stack, stack};
+///
+/// static FALLBACKS: Stack<&str, 4> = stack!["default", "cached", "live"];
+///
+/// assert_eq!(FALLBACKS.peek(), Some(&"live"));
+/// ```
+#[macro_export]
+macro_rules! stack {
+    ($item:expr; $n:expr) => {
+        $crate::stack::Stack::from_array([$item; $n])
+    };
+    ($($item:expr),* $(,)?) => {
+        $crate::stack::Stack::from_array([$($item),*])
+    };
+}
+
 pub struct StackIter<'a, T> {
     data: &'a [MaybeUninit<T>],
     index: usize,
@@ -266,4 +319,42 @@ mod tests {
             Ok(()) => panic!("Should have failed"),
         }
     }
+
+    #[test]
+    fn test_const_construction() {
+        static LAYERS: Stack<u8, 4> = stack![1, 2, 3];
+        const COUNTDOWN: Stack<u8, 3> = {
+            let mut stack = Stack::new();
+            let mut i = 3;
+            while stack.push(i).is_ok() {
+                i -= 1;
+            }
+            stack
+        };
+
+        assert_eq!(
+            LAYERS.iter().copied().collect::<std::vec::Vec<_>>(),
+            [3, 2, 1]
+        );
+        assert_eq!(COUNTDOWN.peek(), Some(&1));
+        assert!(COUNTDOWN.is_full());
+
+        let mut repeated: Stack<i32, 2> = stack![5; 2];
+        assert_eq!(repeated.pop(), Ok(5));
+        assert_eq!(repeated.push(6), Ok(()));
+        assert_eq!(repeated.push(7), Err(StackError::Overflow));
+    }
+
+    #[test]
+    fn test_length_type() {
+        assert_eq!(size_of::<Stack<u8, 8, u8>>(), 9);
+
+        let mut stack = Stack::<u16, 300, u16>::new();
+        for i in 0..300 {
+            stack.push(i).unwrap();
+        }
+        assert_eq!(stack.push(300), Err(StackError::Overflow));
+        assert_eq!(stack.pop(), Ok(299));
+        assert_eq!(stack.iter().len(), 299);
+    }
 }
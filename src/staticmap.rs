@@ -0,0 +1,402 @@
+use core::borrow::Borrow;
+use core::fmt;
+use core::iter::FusedIterator;
+use core::mem::{self, MaybeUninit};
+use core::ptr;
+use core::slice;
+
+use crate::hashmap::Fnv1aHasher;
+
+/// Keys a [`StaticMap`] can be built from in const contexts.
+///
+/// The hash matches what [`HashMap`](crate::HashMap) computes for the same key.
+pub const trait StaticKey: PartialEq {
+    fn static_hash(&self) -> u64;
+}
+
+impl const StaticKey for str {
+    fn static_hash(&self) -> u64 {
+        let mut hasher = Fnv1aHasher::new();
+        hasher.write_bytes(self.as_bytes());
+        hasher.write_bytes(&[0xff]);
+        hasher.state()
+    }
+}
+
+impl<T: ?Sized + [const] StaticKey> const StaticKey for &T {
+    fn static_hash(&self) -> u64 {
+        (**self).static_hash()
+    }
+}
+
+macro_rules! impl_static_key {
+    ($($int:ty),*) => {
+        $(
+            impl const StaticKey for $int {
+                fn static_hash(&self) -> u64 {
+                    let mut hasher = Fnv1aHasher::new();
+                    hasher.write_bytes(&self.to_ne_bytes());
+                    hasher.state()
+                }
+            }
+        )*
+    };
+}
+
+impl_static_key!(
+    u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize
+);
+
+/// Read-only map built at compile time around a minimal perfect hash.
+///
+/// Keys are split into buckets by hash, and each bucket gets a pilot value that
+/// displaces its keys into free slots (the CHD/PTHash scheme), so every key has a
+/// slot of its own. A lookup hashes once, reads one pilot and compares one key,
+/// without ever probing. Built in a `static` or `const`, the whole table lives in
+/// `.rodata`.
+pub struct StaticMap<K, V, const N: usize> {
+    pilots: [u32; N],
+    entries: [(K, V); N],
+}
+
+impl<K: StaticKey, V, const N: usize> StaticMap<K, V, N> {
+    /// Builds the perfect hash over `entries`, usually through [`static_map!`](crate::static_map).
+    ///
+    /// Panics (or fails to compile in a const context) if two keys are equal
+    pub const fn new(entries: [(K, V); N]) -> Self
+    where
+        K: const StaticKey,
+    {
+        let mut hashes = [0u64; N];
+        let mut counts = [0usize; N];
+        let mut i = 0;
+        while i < N {
+            hashes[i] = entries[i].0.static_hash();
+            counts[bucket_of(hashes[i], N)] += 1;
+            i += 1;
+        }
+
+        // group key indices by bucket, so bucket `b` is `order[starts[b]..][..counts[b]]`
+        let mut starts = [0usize; N];
+        let mut largest = 0;
+        let mut offset = 0;
+        let mut bucket = 0;
+        while bucket < N {
+            starts[bucket] = offset;
+            offset += counts[bucket];
+            if counts[bucket] > largest {
+                largest = counts[bucket];
+            }
+            bucket += 1;
+        }
+
+        let mut order = [0usize; N];
+        let mut filled = [0usize; N];
+        i = 0;
+        while i < N {
+            let bucket = bucket_of(hashes[i], N);
+            order[starts[bucket] + filled[bucket]] = i;
+            filled[bucket] += 1;
+            i += 1;
+        }
+
+        // place the biggest buckets first, while the table is still mostly empty
+        let mut pilots = [0u32; N];
+        let mut slots = [0usize; N];
+        let mut taken = [false; N];
+        let mut size = largest;
+        while size > 0 {
+            let mut bucket = 0;
+            while bucket < N {
+                if counts[bucket] == size {
+                    let members = starts[bucket];
+                    check_distinct(&hashes, &order, members, size);
+
+                    let mut pilot = 0u32;
+                    loop {
+                        let mut placed = 0;
+                        while placed < size {
+                            let key = order[members + placed];
+                            let slot = slot_of(hashes[key], pilot, N);
+                            if taken[slot] {
+                                break;
+                            }
+                            taken[slot] = true;
+                            slots[key] = slot;
+                            placed += 1;
+                        }
+
+                        if placed == size {
+                            break;
+                        }
+
+                        while placed > 0 {
+                            placed -= 1;
+                            taken[slots[order[members + placed]]] = false;
+                        }
+                        pilot = pilot
+                            .checked_add(1)
+                            .expect("StaticMap could not find a perfect hash");
+                    }
+                    pilots[bucket] = pilot;
+                }
+                bucket += 1;
+            }
+            size -= 1;
+        }
+
+        let mut table = [const { MaybeUninit::<(K, V)>::uninit() }; N];
+        i = 0;
+        while i < N {
+            table[slots[i]] = MaybeUninit::new(unsafe { ptr::read(&entries[i]) });
+            i += 1;
+        }
+        mem::forget(entries);
+
+        // every slot was written exactly once above
+        let entries = unsafe { ptr::read(table.as_ptr().cast::<[(K, V); N]>()) };
+        Self { pilots, entries }
+    }
+
+    /// Get a reference to a value for a given key
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: StaticKey + ?Sized,
+    {
+        self.get_key_value(key).map(|(_, value)| value)
+    }
+
+    /// Get references to the stored key and its value for a given key
+    pub fn get_key_value<Q>(&self, key: &Q) -> Option<(&K, &V)>
+    where
+        K: Borrow<Q>,
+        Q: StaticKey + ?Sized,
+    {
+        if N == 0 {
+            return None;
+        }
+
+        let hash = key.static_hash();
+        let pilot = self.pilots[bucket_of(hash, N)];
+        let (stored, value) = &self.entries[slot_of(hash, pilot, N)];
+
+        if stored.borrow() == key {
+            Some((stored, value))
+        } else {
+            None
+        }
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: StaticKey + ?Sized,
+    {
+        self.get_key_value(key).is_some()
+    }
+}
+
+impl<K, V, const N: usize> StaticMap<K, V, N> {
+    #[inline]
+    pub const fn len(&self) -> usize {
+        N
+    }
+
+    #[inline]
+    pub const fn is_empty(&self) -> bool {
+        N == 0
+    }
+
+    /// Iterates over the pairs in slot order, which is unrelated to the order given
+    pub fn iter(&self) -> StaticMapIter<'_, K, V> {
+        StaticMapIter {
+            inner: self.entries.iter(),
+        }
+    }
+}
+
+#[inline]
+const fn bucket_of(hash: u64, n: usize) -> usize {
+    ((hash >> 32) as usize) % n
+}
+
+#[inline]
+const fn slot_of(hash: u64, pilot: u32, n: usize) -> usize {
+    // mix the pilot through the whole hash so consecutive pilots land far apart
+    let mut x = hash ^ (pilot as u64).wrapping_mul(0x9e3779b97f4a7c15);
+    x ^= x >> 32;
+    x = x.wrapping_mul(0xd6e8feb86659fd93);
+    x ^= x >> 32;
+    (x % n as u64) as usize
+}
+
+/// Keys with identical hashes can never be told apart by a pilot, so they have to be
+/// the same key
+const fn check_distinct<const N: usize>(
+    hashes: &[u64; N],
+    order: &[usize; N],
+    start: usize,
+    size: usize,
+) {
+    let mut a = 0;
+    while a < size {
+        let mut b = a + 1;
+        while b < size {
+            if hashes[order[start + a]] == hashes[order[start + b]] {
+                panic!("StaticMap keys must be unique");
+            }
+            b += 1;
+        }
+        a += 1;
+    }
+}
+
+impl<K, V, const N: usize> fmt::Debug for StaticMap<K, V, N>
+where
+    K: fmt::Debug,
+    V: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<'a, K, V, const N: usize> IntoIterator for &'a StaticMap<K, V, N> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = StaticMapIter<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Builds a [`StaticMap`](crate::StaticMap) from `key => value` pairs.
+///
+/// ```
+/// use sakurai::{StaticMap, static_map};
+///
+/// static OPCODES: StaticMap<&str, u8, 3> = static_map! {
+///     "nop" => 0x00,
+///     "add" => 0x01,
+///     "sub" => 0x02,
+/// };
+///
+/// assert_eq!(OPCODES.get("add"), Some(&0x01));
+/// ```
+#[macro_export]
+macro_rules! static_map {
+    ($($key:expr => $value:expr),* $(,)?) => {
+        $crate::staticmap::StaticMap::new([$(($key, $value)),*])
+    };
+}
+
+pub struct StaticMapIter<'a, K, V> {
+    inner: slice::Iter<'a, (K, V)>,
+}
+
+impl<'a, K, V> Iterator for StaticMapIter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(key, value)| (key, value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<'a, K, V> DoubleEndedIterator for StaticMapIter<'a, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back().map(|(key, value)| (key, value))
+    }
+}
+
+impl<'a, K, V> ExactSizeIterator for StaticMapIter<'a, K, V> {
+    fn len(&self) -> usize {
+        self.inner.len()
+    }
+}
+
+impl<'a, K, V> FusedIterator for StaticMapIter<'a, K, V> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hashmap::fnv1a_hash;
+    use std::vec::Vec;
+
+    static COMMANDS: StaticMap<&str, u8, 6> = static_map! {
+        "get" => 1,
+        "set" => 2,
+        "del" => 3,
+        "incr" => 4,
+        "decr" => 5,
+        "quit" => 6,
+    };
+
+    #[test]
+    fn test_static_lookup() {
+        assert_eq!(COMMANDS.len(), 6);
+        assert_eq!(COMMANDS.get("get"), Some(&1));
+        assert_eq!(COMMANDS.get("quit"), Some(&6));
+        assert_eq!(COMMANDS.get(&"del"), Some(&3));
+        assert_eq!(COMMANDS.get("nope"), None);
+        assert_eq!(COMMANDS.get(""), None);
+        assert_eq!(COMMANDS.get_key_value("incr"), Some((&"incr", &4)));
+
+        // borrowed lookups don't need a 'static key
+        let input = std::string::String::from("set");
+        assert!(COMMANDS.contains_key(input.as_str()));
+
+        let mut values: Vec<u8> = COMMANDS.iter().map(|(_, value)| *value).collect();
+        values.sort();
+        assert_eq!(values, [1, 2, 3, 4, 5, 6]);
+    }
+
+    #[test]
+    fn test_integer_keys() {
+        const REGISTERS: StaticMap<u16, &str, 4> = static_map! {
+            0x00 => "status",
+            0x04 => "control",
+            0x08 => "data",
+            0x0c => "irq",
+        };
+
+        assert_eq!(REGISTERS.get(&0x08), Some(&"data"));
+        assert_eq!(REGISTERS.get(&0x01), None);
+    }
+
+    #[test]
+    fn test_empty() {
+        let map: StaticMap<u32, u32, 0> = static_map! {};
+        assert!(map.is_empty());
+        assert_eq!(map.get(&0), None);
+        assert_eq!(map.iter().count(), 0);
+    }
+
+    #[test]
+    fn test_many_keys() {
+        let map =
+            StaticMap::<u32, u32, 500>::new(core::array::from_fn(|i| (i as u32 * 7, i as u32)));
+
+        for i in 0..500 {
+            assert_eq!(map.get(&(i * 7)), Some(&i));
+            assert_eq!(map.get(&(i * 7 + 1)), None);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "StaticMap keys must be unique")]
+    fn test_duplicate_keys() {
+        let _ = StaticMap::new([("a", 1), ("b", 2), ("a", 3)]);
+    }
+
+    #[test]
+    fn test_hash_matches_hashmap() {
+        assert_eq!("quit".static_hash(), fnv1a_hash("quit"));
+        assert_eq!(42u64.static_hash(), fnv1a_hash(&42u64));
+        assert_eq!((-3i8).static_hash(), fnv1a_hash(&-3i8));
+    }
+}